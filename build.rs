@@ -1,10 +1,36 @@
 // my_ssr_server/build.rs
 // This script runs automatically before your main application is compiled.
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::env;
 use std::path::{Path, PathBuf};
-use fs_extra::dir::{copy, CopyOptions};
-use fs_extra::copy_items; // Import `copy_items` for copying contents of a directory
+use sha2::{Digest, Sha256};
+use std::io::Write;
+
+#[path = "build/minify.rs"]
+mod minify;
+#[path = "build/scope_css.rs"]
+mod scope_css;
+
+// Name of the manifest mapping logical asset names to fingerprinted names.
+// It is written to OUT_DIR and compiled into the server binary.
+const ASSET_MANIFEST_FILE_NAME: &str = "asset-manifest.json";
+
+// Number of hex characters of the content hash kept in fingerprinted names.
+const FINGERPRINT_LENGTH: usize = 8;
+
+// Generated when the `embed` feature is on: a table of every staged asset,
+// compiled into the binary by `src/embedded.rs`.
+const EMBEDDED_ASSETS_FILE_NAME: &str = "embedded_assets.rs";
+
+// Folder (next to the binary) the assets are staged into. Each staging layout
+// gets its own folder, so builds with different features never prune each
+// other's files; the server finds it through the `STAGED_STATIC_DIR` variable.
+const STATIC_DIR_NAME: &str = "static";
+const NAMESPACED_STATIC_DIR_NAME: &str = "static-namespaced";
+
+// Extensions worth precompressing; images and fonts are already compressed.
+const COMPRESSIBLE_EXTENSIONS: &[&str] = &["css", "js", "html", "svg", "json", "txt", "xml", "ico", "map"];
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 1. Tell Cargo to re-run this build script if relevant directories change.
@@ -15,17 +41,40 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Get the path to the root of the main crate (my_ssr_server)
     let main_crate_manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?);
 
-    // Destination for all static files (temporary staging area)
-    let main_crate_static_dest_dir = main_crate_manifest_dir.join("static");
-    std::fs::create_dir_all(&main_crate_static_dest_dir)?; // Ensure it exists
+    // Every output path (relative to `static/`) with the source files that produce it.
+    // More than one source for the same output is a collision, see Stage 2b.
+    // This is the exact set of assets the build publishes; anything else found
+    // in the output folder is stale and gets pruned in Stage 7.
+    let mut staged_assets: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
 
-    let mut copy_options = CopyOptions::new();
-    copy_options.overwrite = true;
-    copy_options.buffer_size = 64000;
+    // With the `namespaced-assets` feature, component files are staged under
+    // `components/<component>/` instead of being flattened into `static/`.
+    let namespace_components = env::var_os("CARGO_FEATURE_NAMESPACED_ASSETS").is_some();
 
-    // --- Stage 1: Copy static files from pages_components/src/components/*/ to my_ssr_server/static ---
+    // Component stylesheets (output path -> component name); Stage 3 scopes their
+    // selectors to the component's root element.
+    let mut component_styles: BTreeMap<String, String> = BTreeMap::new();
+
+    // --- Stage 0: Collect the site-wide files kept in my_ssr_server/static ---
+    let global_static_source_dir = main_crate_manifest_dir.join("static");
+
+    if global_static_source_dir.is_dir() {
+        for relative_path in relative_file_paths(&global_static_source_dir)? {
+            let file_path = global_static_source_dir.join(&relative_path);
+            staged_assets.entry(relative_path).or_default().push(file_path);
+        }
+    } else {
+        println!(
+            "cargo:warning=Main crate static directory not found at {:?}",
+            global_static_source_dir
+        );
+    }
+
+
+    // --- Stage 1: Collect static files from pages_components/src/components/*/ ---
+    // Everything but the `.html` templates is public; the templates are compiled
+    // into the components with `include_str!` and never served.
     let components_base_source_dir = main_crate_manifest_dir.join("pages_components").join("src").join("components");
-    let mut component_static_files_to_copy: Vec<PathBuf> = Vec::new();
 
     if components_base_source_dir.exists() && components_base_source_dir.is_dir() {
         println!("cargo:warning=Collecting static files from pages_components...");
@@ -34,14 +83,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             if component_path.is_dir() {
                 // This is a component directory (e.g., button_component)
                 println!("cargo:warning=  Processing component directory: {:?}", component_path);
+                let component_name = utf8_file_name(&component_path)?;
                 for file_entry in std::fs::read_dir(&component_path)? {
                     let file_path = file_entry?.path();
-                    if file_path.is_file() {
-                        if let Some(extension) = file_path.extension() {
-                            if extension == "html" || extension == "css" || extension == "js" {
-                                component_static_files_to_copy.push(file_path);
-                            }
+                    let extension = file_path.extension();
+                    if file_path.is_file() && extension.is_none_or(|extension| extension != "html") {
+                        let file_name = utf8_file_name(&file_path)?;
+                        let output = if namespace_components {
+                            format!("components/{}/{}", component_name, file_name)
+                        } else {
+                            file_name
+                        };
+                        if extension.is_some_and(|extension| extension == "css") {
+                            component_styles.insert(output.clone(), component_name.clone());
                         }
+                        staged_assets.entry(output).or_default().push(file_path);
                     }
                 }
             }
@@ -53,44 +109,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         );
     }
 
-    if !component_static_files_to_copy.is_empty() {
-        println!(
-            "cargo:warning=Copying pages_components static files to {:?}",
-            main_crate_static_dest_dir
-        );
-        // `copy_items` copies individual files from a list to a destination directory.
-        // This ensures no nested component directories in the main static folder.
-        copy_items(
-            &component_static_files_to_copy,
-            &main_crate_static_dest_dir,
-            &copy_options,
-        )?;
-    } else {
-        println!("cargo:warning=No static files found in pages_components to copy.");
-    }
 
-
-    // --- Stage 2: Copy static files from html_pages/static to my_ssr_server/static ---
+    // --- Stage 2: Collect static files from html_pages/static ---
     let html_pages_static_source_dir = main_crate_manifest_dir.join("html_pages").join("static");
 
-    let mut html_page_static_files_to_copy: Vec<PathBuf> = Vec::new();
     if html_pages_static_source_dir.exists() && html_pages_static_source_dir.is_dir() {
         for entry in std::fs::read_dir(&html_pages_static_source_dir)? {
-            let entry = entry?;
-            html_page_static_files_to_copy.push(entry.path());
+            let file_path = entry?.path();
+            if file_path.is_file() {
+                staged_assets.entry(utf8_file_name(&file_path)?).or_default().push(file_path);
+            }
         }
-    }
-
-    if !html_page_static_files_to_copy.is_empty() {
-        println!(
-            "cargo:warning=Copying html_pages static files to {:?}",
-            main_crate_static_dest_dir
-        );
-        copy_items(
-            &html_page_static_files_to_copy,
-            &main_crate_static_dest_dir,
-            &copy_options,
-        )?;
     } else {
         println!(
             "cargo:warning=html_pages/static directory is empty or not found at {:?}",
@@ -99,36 +128,320 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
 
-    // --- Stage 3: Copy combined static files from my_ssr_server/static to target/debug or target/release ---
-    let combined_static_source_dir = main_crate_static_dest_dir; // This is now the source for the final copy
+    // --- Stage 2b: Refuse to let two sources write the same output file ---
+    let collisions: Vec<String> = staged_assets
+        .iter()
+        .filter(|(_, sources)| sources.len() > 1)
+        .map(|(output, sources)| {
+            let origins: Vec<String> = sources.iter().map(|source| format!("    {}", source.display())).collect();
+            format!("  static/{} would be written by:\n{}", output, origins.join("\n"))
+        })
+        .collect();
+    if !collisions.is_empty() {
+        return Err(format!(
+            "Static asset name collision:\n{}\nRename the files, or enable the `namespaced-assets` feature \
+             to stage component files under static-namespaced/components/<component>/.",
+            collisions.join("\n")
+        )
+        .into());
+    }
+
+
+    // --- Stage 3: Copy the collected files to target/<profile>/static (or static-namespaced) ---
     let target_dir_str = env::var("CARGO_TARGET_DIR")
         .unwrap_or_else(|_| "target".to_string());
     let target_dir = PathBuf::from(target_dir_str);
     let profile = env::var("PROFILE")?;
     let build_output_dir = target_dir.join(&profile);
+    let static_dir_name = if namespace_components {
+        NAMESPACED_STATIC_DIR_NAME
+    } else {
+        STATIC_DIR_NAME
+    };
+    println!("cargo:rustc-env=STAGED_STATIC_DIR={}", static_dir_name);
+    let fingerprinted_dest_dir = build_output_dir.join(static_dir_name);
+    std::fs::create_dir_all(&fingerprinted_dest_dir)?;
 
-    std::fs::create_dir_all(&build_output_dir)?;
+    // Every file this build writes to the output folder; Stage 7 removes the rest.
+    let mut produced_files: BTreeSet<String> = BTreeSet::new();
 
-    // For Stage 3, we want to copy the 'static' folder itself into the target.
-    // So, 'copy_inside' should be false.
-    copy_options.copy_inside = false; // Re-use and modify options
+    println!(
+        "cargo:warning=Copying {} static files to {:?}",
+        staged_assets.len(),
+        fingerprinted_dest_dir
+    );
+    // Component stylesheets are scoped to their component (build/scope_css.rs).
+    // Release builds then minify CSS and JS and write a `.map` next to each; debug
+    // builds copy the sources as they are otherwise.
+    let minify_assets = profile == "release";
+    let mut source_maps: Vec<String> = Vec::new();
 
-    if combined_static_source_dir.exists() {
-        println!(
-            "cargo:warning=Copying combined static files from {:?} to {:?}",
-            combined_static_source_dir, build_output_dir
-        );
-        copy( // Use `copy` here as we want to copy the directory itself
-            &combined_static_source_dir,
-            &build_output_dir,
-            &copy_options,
+    let mut scoped_count = 0;
+
+    for (output, sources) in &staged_assets {
+        let source = &sources[0];
+        let extension = source.extension().and_then(|extension| extension.to_str());
+        let source_name = source
+            .strip_prefix(&main_crate_manifest_dir)
+            .unwrap_or(source)
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        // `source_code` is the file as written, `code` what gets staged from it.
+        let source_code = match extension {
+            Some("css" | "js") => Some(std::fs::read_to_string(source)?),
+            _ => None,
+        };
+        let mut code = source_code.clone();
+        if let Some(component_name) = component_styles.get(output)
+            && let Some(css) = &code
+            && !scope_css::is_unscoped(css)
+        {
+            let scoped = scope_css::scope_css(css, component_name)
+                .map_err(|error| format!("Could not scope {}: {}", source_name, error))?;
+            code = Some(scoped);
+            scoped_count += 1;
+        }
+
+        // The source maps embed `source_code`, so the dev tools show the file as
+        // written rather than its scoped selectors.
+        let minified = match (&source_code, &code, extension) {
+            (Some(source_code), Some(code), Some("css")) if minify_assets => {
+                Some(minify::minify_css(output, &source_name, source_code, code)?)
+            }
+            (Some(source_code), Some(_), Some("js")) if minify_assets => {
+                Some(minify::minify_js(output, &source_name, source_code)?)
+            }
+            _ => None,
+        };
+
+        match minified {
+            Some(minified) => {
+                let source_map = format!("{}.map", output);
+                write_if_changed(&fingerprinted_dest_dir.join(output), minified.code.as_bytes())?;
+                write_if_changed(&fingerprinted_dest_dir.join(&source_map), minified.source_map.as_bytes())?;
+                produced_files.insert(source_map.clone());
+                source_maps.push(source_map);
+            }
+            None => match &code {
+                Some(code) => write_if_changed(&fingerprinted_dest_dir.join(output), code.as_bytes())?,
+                None => write_if_changed(&fingerprinted_dest_dir.join(output), &std::fs::read(source)?)?,
+            },
+        }
+        produced_files.insert(output.clone());
+    }
+    println!("cargo:warning=Scoped {} component stylesheets", scoped_count);
+    if minify_assets {
+        println!("cargo:warning=Minified {} stylesheets and scripts", source_maps.len());
+    }
+
+
+    // --- Stage 4: Fingerprint every staged asset and write the manifest ---
+    // Each file gets a content-hashed sibling (e.g. `button_component.3f9a1c2b.css`)
+    // next to its original, so changed files get new URLs and can be cached forever.
+    // The manifest (`button_component.css` -> `button_component.3f9a1c2b.css`) is what
+    // the components resolve their tags through at runtime.
+    // Files staged under a namespace are listed under their path
+    // (`components/button_component/button_component.css`), the name components
+    // register them with under the same feature (see `component_asset_name`).
+    let mut fingerprinted_files: BTreeMap<String, String> = BTreeMap::new();
+
+    for relative_path in staged_assets.keys() {
+        let contents = std::fs::read(fingerprinted_dest_dir.join(relative_path))?;
+        let fingerprinted_path = match relative_path.rsplit_once('/') {
+            Some((directory, file_name)) => {
+                format!("{}/{}", directory, fingerprinted_file_name(file_name, &contents))
+            }
+            None => fingerprinted_file_name(relative_path, &contents),
+        };
+        write_if_changed(&fingerprinted_dest_dir.join(&fingerprinted_path), &contents)?;
+        produced_files.insert(fingerprinted_path.clone());
+        fingerprinted_files.insert(relative_path.clone(), fingerprinted_path);
+    }
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+    std::fs::write(
+        out_dir.join(ASSET_MANIFEST_FILE_NAME),
+        serde_json::to_string_pretty(&fingerprinted_files)?,
+    )?;
+    println!(
+        "cargo:warning=Fingerprinted {} assets into {:?}",
+        fingerprinted_files.len(),
+        fingerprinted_dest_dir
+    );
+
+
+    // --- Stage 5: Precompress the staged assets ---
+    // `.gz` and `.br` siblings are written next to every compressible file (original
+    // and fingerprinted), so the server can pick one based on `Accept-Encoding`
+    // instead of compressing on every request. A variant that isn't smaller than
+    // the original is not written.
+    let mut precompressed_count = 0;
+    for file_name in fingerprinted_files.keys().chain(fingerprinted_files.values()).chain(&source_maps) {
+        let file_path = fingerprinted_dest_dir.join(file_name);
+        let is_compressible = file_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| COMPRESSIBLE_EXTENSIONS.contains(&extension));
+        if !is_compressible {
+            continue;
+        }
+
+        let contents = std::fs::read(&file_path)?;
+        for (suffix, compressed) in [("gz", gzip(&contents)?), ("br", brotli(&contents)?)] {
+            let compressed_path = fingerprinted_dest_dir.join(format!("{}.{}", file_name, suffix));
+            if compressed.len() < contents.len() {
+                write_if_changed(&compressed_path, &compressed)?;
+                produced_files.insert(format!("{}.{}", file_name, suffix));
+                precompressed_count += 1;
+            }
+        }
+    }
+    println!(
+        "cargo:warning=Wrote {} precompressed variants into {:?}",
+        precompressed_count, fingerprinted_dest_dir
+    );
+
+
+    // --- Stage 6 (feature "embed"): Generate the table of embedded assets ---
+    // Lists every file staged above (originals, fingerprinted copies, source maps and their
+    // precompressed variants) with `include_bytes!`, so the binary can serve them
+    // from memory without a `static/` folder next to it.
+    if env::var_os("CARGO_FEATURE_EMBED").is_some() {
+        let mut embedded_entries = String::new();
+        let mut embedded_count = 0;
+        for file_name in fingerprinted_files.keys().chain(fingerprinted_files.values()).chain(&source_maps) {
+            for served_name in [
+                file_name.clone(),
+                format!("{}.gz", file_name),
+                format!("{}.br", file_name),
+            ] {
+                let file_path = fingerprinted_dest_dir.join(&served_name);
+                if !file_path.is_file() {
+                    continue;
+                }
+                let file_path = file_path.canonicalize()?;
+                let digest = Sha256::digest(std::fs::read(&file_path)?);
+                let etag: String = digest.iter().take(8).map(|byte| format!("{:02x}", byte)).collect();
+                embedded_entries.push_str(&format!(
+                    "    EmbeddedAsset {{ name: {:?}, etag: {:?}, contents: include_bytes!({:?}) }},\n",
+                    served_name, etag, file_path
+                ));
+                embedded_count += 1;
+            }
+        }
+        std::fs::write(
+            out_dir.join(EMBEDDED_ASSETS_FILE_NAME),
+            format!("pub static EMBEDDED_ASSETS: &[EmbeddedAsset] = &[\n{}];\n", embedded_entries),
         )?;
-    } else {
+        println!("cargo:warning=Embedding {} assets into the binary", embedded_count);
+    }
+
+
+    // --- Stage 7: Prune files no source produced anymore ---
+    // Deleting `task_card_001.js` from its component, or editing it (which changes
+    // its fingerprint), must not leave the old copy behind to be served forever.
+    // Only this layout's folder is pruned; the other one belongs to other builds.
+    let mut pruned_count = 0;
+    for relative_path in relative_file_paths(&fingerprinted_dest_dir)? {
+        if !produced_files.contains(&relative_path) {
+            std::fs::remove_file(fingerprinted_dest_dir.join(&relative_path))?;
+            pruned_count += 1;
+        }
+    }
+    remove_empty_directories(&fingerprinted_dest_dir)?;
+    if pruned_count > 0 {
         println!(
-            "cargo:warning=Main crate static directory not found at {:?}",
-            combined_static_source_dir
+            "cargo:warning=Pruned {} stale files from {:?}",
+            pruned_count, fingerprinted_dest_dir
         );
     }
 
     Ok(())
+}
+
+/// Writes `contents` to `path` (creating its parent folders) unless the file
+/// already holds exactly those bytes, so unchanged assets keep their
+/// modification time and with it their `ETag`.
+fn write_if_changed(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if std::fs::read(path).is_ok_and(|existing| existing == contents) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, contents)
+}
+
+/// Removes the folders under `directory` left empty by pruning.
+fn remove_empty_directories(directory: &Path) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            remove_empty_directories(&path)?;
+            if std::fs::read_dir(&path)?.next().is_none() {
+                std::fs::remove_dir(&path)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn utf8_file_name(path: &Path) -> Result<String, String> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(String::from)
+        .ok_or_else(|| format!("Non UTF-8 asset file name: {:?}", path))
+}
+
+/// Paths of every file under `directory`, relative to it and `/`-separated.
+fn relative_file_paths(directory: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        let name = utf8_file_name(&path)?;
+        if path.is_dir() {
+            for nested in relative_file_paths(&path)? {
+                paths.push(format!("{}/{}", name, nested));
+            }
+        } else if path.is_file() {
+            paths.push(name);
+        }
+    }
+    Ok(paths)
+}
+
+fn gzip(contents: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(contents)?;
+    encoder.finish()
+}
+
+fn brotli(contents: &[u8]) -> std::io::Result<Vec<u8>> {
+    let params = brotli::enc::BrotliEncoderParams {
+        quality: 11,
+        ..Default::default()
+    };
+    let mut compressed = Vec::new();
+    brotli::BrotliCompress(&mut &contents[..], &mut compressed, &params)?;
+    Ok(compressed)
+}
+
+/// Inserts a short content hash before the extension:
+/// `button_component.css` -> `button_component.3f9a1c2b.css`, `LICENSE` -> `LICENSE.3f9a1c2b`.
+fn fingerprinted_file_name(file_name: &str, contents: &[u8]) -> String {
+    let digest = Sha256::digest(contents);
+    let hash: String = digest
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>()[..FINGERPRINT_LENGTH]
+        .to_string();
+
+    match Path::new(file_name).extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => {
+            let stem = &file_name[..file_name.len() - extension.len() - 1];
+            format!("{}.{}.{}", stem, hash, extension)
+        }
+        None => format!("{}.{}", file_name, hash),
+    }
 }
\ No newline at end of file
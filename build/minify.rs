@@ -0,0 +1,550 @@
+// my_ssr_server/build/minify.rs
+// CSS and JS minification for release builds, used by build.rs.
+//
+// Every minified file ends with a `sourceMappingURL` comment pointing at a
+// `<file>.map` sibling, and the map embeds the original source, so the browser
+// dev tools show the readable file.
+
+use lightningcss::stylesheet::{MinifyOptions, ParserOptions, PrinterOptions, StyleSheet};
+use parcel_sourcemap::{OriginalLocation, SourceMap};
+
+/// A minified file and the JSON of its source map.
+pub struct Minified {
+    pub code: String,
+    pub source_map: String,
+}
+
+/// Minifies a stylesheet with lightningcss.
+/// `file_name` is the served name (`button_component.css`), `source_name` the
+/// path shown in the dev tools, `source_code` the file as written and `code`
+/// what gets minified (`source_code` after scoping, whose line breaks it keeps).
+pub fn minify_css(file_name: &str, source_name: &str, source_code: &str, code: &str) -> Result<Minified, String> {
+    let error = |error: String| format!("Could not minify {}: {}", source_name, error);
+
+    let mut stylesheet = StyleSheet::parse(
+        code,
+        ParserOptions {
+            filename: source_name.to_string(),
+            ..ParserOptions::default()
+        },
+    )
+    .map_err(|e| error(e.to_string()))?;
+    stylesheet
+        .minify(MinifyOptions::default())
+        .map_err(|e| error(e.to_string()))?;
+
+    let mut source_map = new_source_map(source_name, source_code)?;
+    let result = stylesheet
+        .to_css(PrinterOptions {
+            minify: true,
+            source_map: Some(&mut source_map),
+            ..PrinterOptions::default()
+        })
+        .map_err(|e| error(e.to_string()))?;
+
+    Ok(Minified {
+        code: format!("{}\n/*# sourceMappingURL={}.map */\n", result.code, map_url(file_name)),
+        source_map: source_map.to_json(None).map_err(|e| error(e.to_string()))?,
+    })
+}
+
+/// Minifies a script by dropping comments and redundant whitespace.
+///
+/// Tokens are never rewritten. A line break between two tokens is kept (as a
+/// single `\n`) only where automatic semicolon insertion could end a statement
+/// there, so the minified script parses as the same program.
+pub fn minify_js(file_name: &str, source_name: &str, code: &str) -> Result<Minified, String> {
+    let tokens = tokenize(code).map_err(|e| format!("Could not minify {}: {}", source_name, e))?;
+
+    let mut source_map = new_source_map(source_name, code)?;
+    let mut output = String::with_capacity(code.len());
+    let (mut line, mut column) = (0u32, 0u32);
+    let mut previous: Option<&Token> = None;
+
+    for token in &tokens {
+        if let Some(previous) = previous {
+            if token.newline_before && newline_matters(previous, token) {
+                output.push('\n');
+                line += 1;
+                column = 0;
+            } else if token.space_before && needs_space(previous, token) {
+                output.push(' ');
+                column += 1;
+            }
+        }
+        source_map.add_mapping(line, column, Some(OriginalLocation::new(token.line, token.column, 0, None)));
+        output.push_str(token.text);
+        match token.text.rsplit_once('\n') {
+            // Multi-line template literals continue on a later output line.
+            Some((before, after)) => {
+                line += before.matches('\n').count() as u32 + 1;
+                column = utf16_length(after);
+            }
+            None => column += utf16_length(token.text),
+        }
+        previous = Some(token);
+    }
+
+    Ok(Minified {
+        code: format!("{}\n//# sourceMappingURL={}.map\n", output, map_url(file_name)),
+        source_map: source_map
+            .to_json(None)
+            .map_err(|e| format!("Could not write the source map of {}: {}", source_name, e))?,
+    })
+}
+
+fn new_source_map(source_name: &str, code: &str) -> Result<SourceMap, String> {
+    let mut source_map = SourceMap::new("/");
+    let source = source_map.add_source(source_name);
+    source_map
+        .set_source_content(source as usize, code)
+        .map_err(|e| format!("Could not start the source map of {}: {}", source_name, e))?;
+    Ok(source_map)
+}
+
+/// The map is a sibling of the file, so the URL is relative to its folder.
+fn map_url(file_name: &str) -> &str {
+    file_name.rsplit('/').next().unwrap_or(file_name)
+}
+
+// Source map columns count UTF-16 code units.
+fn utf16_length(text: &str) -> u32 {
+    text.encode_utf16().count() as u32
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    Word,
+    Number,
+    Punctuator,
+    /// Strings, template literals and regular expressions, copied verbatim.
+    Literal,
+}
+
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    // Zero-based position in the source, as source maps count them.
+    line: u32,
+    column: u32,
+    space_before: bool,
+    newline_before: bool,
+    /// Whether the token can end an expression (`x`, `1`, `)` of a call, `]`,
+    /// `}` of an object, postfix `++`). A `/` after it is a division, and a
+    /// statement may end after it, so a line break there can matter.
+    ends_value: bool,
+}
+
+// Keywords after which a `/` starts a regular expression rather than a division.
+const REGEX_PREFIX_KEYWORDS: &[&str] = &[
+    "return", "typeof", "instanceof", "in", "of", "new", "delete", "void", "throw", "case", "do", "else", "yield",
+    "await",
+];
+
+// Keywords that can't be followed by a line break without ending the statement
+// (`return\nx` returns `undefined`).
+const RESTRICTED_KEYWORDS: &[&str] = &["return", "throw", "yield"];
+
+// Keywords whose parenthesized part is followed by a statement: `if (a) /re/`.
+const CONDITION_KEYWORDS: &[&str] = &["if", "while", "for", "with"];
+
+// Keywords followed by a block rather than an object literal: `else { }`.
+const BLOCK_KEYWORDS: &[&str] = &["else", "do", "try", "finally", "static"];
+
+// Punctuators that can't start a statement; ASI never happens before them, so a
+// line break in front of them is dropped.
+const CONTINUING_PUNCTUATORS: &[&str] = &[")", "]", "}", ",", ";", ".", "?", ":", "=", "*", "%", "&", "|", "^", "<", ">"];
+
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'$' || byte == b'\\' || byte >= 0x80
+}
+
+/// Whether dropping the whitespace between two tokens would change their meaning
+/// (`let x`, `a + +b`, `1 .toString()`, `a / /re/`).
+fn needs_space(previous: &Token, next: &Token) -> bool {
+    let last = previous.text.as_bytes()[previous.text.len() - 1];
+    let first = next.text.as_bytes()[0];
+    (is_word_byte(last) && is_word_byte(first))
+        || (last == first && matches!(last, b'+' | b'-' | b'/'))
+        || (last == b'/' && first == b'*')
+        || (previous.kind == TokenKind::Number && first == b'.')
+}
+
+/// Whether a line break between two tokens may end a statement. It can't after
+/// an operator or an opening bracket (`a =\nb`), nor before a token that never
+/// starts a statement (`a\n.b`), except after `return` and its kind.
+///
+/// Every `}` counts as a possible end: the brace stack can't tell the body of a
+/// function expression (`x = function () {}\nfoo()`) from a block.
+fn newline_matters(previous: &Token, next: &Token) -> bool {
+    if previous.kind == TokenKind::Word && RESTRICTED_KEYWORDS.contains(&previous.text) {
+        return true;
+    }
+    if next.kind == TokenKind::Punctuator && CONTINUING_PUNCTUATORS.contains(&next.text) {
+        return false;
+    }
+    previous.ends_value || previous.text == "}"
+}
+
+fn regex_allowed(previous: Option<&Token>) -> bool {
+    previous.is_none_or(|token| !token.ends_value)
+}
+
+fn tokenize(code: &str) -> Result<Vec<Token<'_>>, String> {
+    let bytes = code.as_bytes();
+    let mut tokens: Vec<Token> = Vec::new();
+    let (mut position, mut line, mut line_start) = (0, 0u32, 0);
+    let (mut space_before, mut newline_before) = (false, false);
+    // Open `(`: whether each follows `if`/`while`/`for`/`with`.
+    let mut parens: Vec<bool> = Vec::new();
+    // Open `{`: whether each starts a block rather than an object literal.
+    let mut braces: Vec<bool> = Vec::new();
+
+    while position < bytes.len() {
+        let byte = bytes[position];
+        let next = bytes.get(position + 1).copied();
+
+        // Whitespace and comments only leave a trace in the two flags.
+        if byte == b'\n' {
+            (space_before, newline_before) = (true, true);
+            position += 1;
+            line += 1;
+            line_start = position;
+            continue;
+        }
+        if byte.is_ascii_whitespace() {
+            space_before = true;
+            position += 1;
+            continue;
+        }
+        if byte == b'/' && next == Some(b'/') {
+            space_before = true;
+            while position < bytes.len() && bytes[position] != b'\n' {
+                position += 1;
+            }
+            continue;
+        }
+        if byte == b'/' && next == Some(b'*') {
+            let end = code[position + 2..]
+                .find("*/")
+                .map(|offset| position + 2 + offset + 2)
+                .ok_or_else(|| format!("line {}: unterminated comment", line + 1))?;
+            space_before = true;
+            for (offset, _) in code[position..end].match_indices('\n') {
+                newline_before = true;
+                line += 1;
+                line_start = position + offset + 1;
+            }
+            position = end;
+            continue;
+        }
+
+        let start = position;
+        let previous = tokens.last();
+        let kind = match byte {
+            b'"' | b'\'' => {
+                position = skip_string(bytes, position)
+                    .ok_or_else(|| format!("line {}: unterminated string", line + 1))?;
+                TokenKind::Literal
+            }
+            b'`' => {
+                position = skip_template(bytes, position)
+                    .ok_or_else(|| format!("line {}: unterminated template literal", line + 1))?;
+                TokenKind::Literal
+            }
+            b'/' if regex_allowed(previous) => {
+                position = skip_regex(bytes, position)
+                    .ok_or_else(|| format!("line {}: unterminated regular expression", line + 1))?;
+                TokenKind::Literal
+            }
+            b'0'..=b'9' => {
+                position = skip_number(bytes, position);
+                TokenKind::Number
+            }
+            b'.' if next.is_some_and(|next| next.is_ascii_digit()) => {
+                position = skip_number(bytes, position);
+                TokenKind::Number
+            }
+            _ if is_word_byte(byte) => {
+                while position < bytes.len() && is_word_byte(bytes[position]) {
+                    position += 1;
+                }
+                TokenKind::Word
+            }
+            _ => {
+                position += 1;
+                TokenKind::Punctuator
+            }
+        };
+
+        let text = &code[start..position];
+        let ends_value = match kind {
+            TokenKind::Number | TokenKind::Literal => true,
+            TokenKind::Word => !REGEX_PREFIX_KEYWORDS.contains(&text),
+            TokenKind::Punctuator => match text {
+                "(" => {
+                    let follows_keyword =
+                        previous.is_some_and(|token| token.kind == TokenKind::Word && CONDITION_KEYWORDS.contains(&token.text));
+                    parens.push(follows_keyword);
+                    false
+                }
+                ")" => !parens.pop().unwrap_or(false),
+                "{" => {
+                    braces.push(starts_block(previous));
+                    false
+                }
+                "}" => !braces.pop().unwrap_or(true),
+                "]" => true,
+                // Postfix `a++`: the second `+` directly after a first one that
+                // directly follows a value.
+                "+" | "-" => {
+                    let before = tokens.len().checked_sub(2).map(|index| &tokens[index]);
+                    !space_before
+                        && previous.is_some_and(|token| token.text == text && !token.newline_before)
+                        && !previous.is_some_and(|token| token.ends_value)
+                        && before.is_some_and(|token| token.ends_value)
+                }
+                _ => false,
+            },
+        };
+        tokens.push(Token {
+            kind,
+            text,
+            line,
+            column: utf16_length(&code[line_start..start]),
+            space_before,
+            newline_before,
+            ends_value,
+        });
+        (space_before, newline_before) = (false, false);
+        for (offset, _) in text.match_indices('\n') {
+            line += 1;
+            line_start = start + offset + 1;
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Whether a `{` after `previous` opens a block (`if (a) {`, `else {`, `=> {`)
+/// rather than an object literal (`= {`, `({`, `return {`).
+fn starts_block(previous: Option<&Token>) -> bool {
+    match previous {
+        None => true,
+        Some(token) => match token.kind {
+            TokenKind::Punctuator => matches!(token.text, ";" | "{" | "}" | ")" | ">"),
+            TokenKind::Word => BLOCK_KEYWORDS.contains(&token.text),
+            TokenKind::Number | TokenKind::Literal => false,
+        },
+    }
+}
+
+/// End (exclusive) of the string literal starting at `start`.
+fn skip_string(bytes: &[u8], start: usize) -> Option<usize> {
+    let quote = bytes[start];
+    let mut position = start + 1;
+    while position < bytes.len() {
+        match bytes[position] {
+            b'\\' => position += 2,
+            b'\n' => return None,
+            byte if byte == quote => return Some(position + 1),
+            _ => position += 1,
+        }
+    }
+    None
+}
+
+/// End (exclusive) of the template literal starting at `start`, `${...}` included.
+fn skip_template(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut position = start + 1;
+    while position < bytes.len() {
+        match bytes[position] {
+            b'\\' => position += 2,
+            b'`' => return Some(position + 1),
+            b'$' if bytes.get(position + 1) == Some(&b'{') => {
+                position = skip_substitution(bytes, position + 2)?;
+            }
+            _ => position += 1,
+        }
+    }
+    None
+}
+
+/// End (exclusive) of a `${...}` substitution whose body starts at `start`.
+fn skip_substitution(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 1;
+    let mut position = start;
+    while position < bytes.len() {
+        match bytes[position] {
+            b'"' | b'\'' => position = skip_string(bytes, position)?,
+            b'`' => position = skip_template(bytes, position)?,
+            b'{' => {
+                depth += 1;
+                position += 1;
+            }
+            b'}' => {
+                depth -= 1;
+                position += 1;
+                if depth == 0 {
+                    return Some(position);
+                }
+            }
+            _ => position += 1,
+        }
+    }
+    None
+}
+
+/// End (exclusive) of the regular expression literal starting at `start`, flags included.
+fn skip_regex(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut position = start + 1;
+    let mut in_class = false;
+    while position < bytes.len() {
+        match bytes[position] {
+            b'\\' => position += 2,
+            b'\n' => return None,
+            b'[' => {
+                in_class = true;
+                position += 1;
+            }
+            b']' => {
+                in_class = false;
+                position += 1;
+            }
+            b'/' if !in_class => {
+                position += 1;
+                while position < bytes.len() && is_word_byte(bytes[position]) {
+                    position += 1;
+                }
+                return Some(position);
+            }
+            _ => position += 1,
+        }
+    }
+    None
+}
+
+/// End (exclusive) of the numeric literal starting at `start`.
+fn skip_number(bytes: &[u8], start: usize) -> usize {
+    let is_hex = bytes[start] == b'0' && matches!(bytes.get(start + 1), Some(b'x' | b'X'));
+    let mut position = start;
+    while position < bytes.len() {
+        let byte = bytes[position];
+        let is_exponent_sign =
+            matches!(byte, b'+' | b'-') && !is_hex && matches!(bytes[position - 1], b'e' | b'E');
+        if !(byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'.' || is_exponent_sign) {
+            break;
+        }
+        position += 1;
+    }
+    position
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn js(code: &str) -> String {
+        let minified = minify_js("app.js", "src/app.js", code).unwrap();
+        let (code, _) = minified.code.rsplit_once("\n//# sourceMappingURL=").unwrap();
+        code.to_string()
+    }
+
+    #[test]
+    fn comments_and_indentation_are_dropped() {
+        assert_eq!(js("// intro\nlet  x = 1; /* note */\n\tx += 2;"), "let x=1;x+=2;");
+        assert_eq!(js("a /* note */ + b"), "a+b");
+        // A comment spanning lines counts as a line break.
+        assert_eq!(js("return /* two\nlines */ x"), "return\nx");
+    }
+
+    #[test]
+    fn spaces_that_separate_tokens_are_kept() {
+        assert_eq!(js("a - -b; c + +d; e + ++f"), "a- -b;c+ +d;e+ ++f");
+        assert_eq!(js("return typeof x"), "return typeof x");
+        assert_eq!(js("1 .toString()"), "1 .toString()");
+    }
+
+    #[test]
+    fn slash_after_a_condition_starts_a_regex() {
+        assert_eq!(js("if (a) /'/.test(s)"), "if(a)/'/.test(s)");
+        assert_eq!(js("while (x) /[)]/g.exec(s)"), "while(x)/[)]/g.exec(s)");
+    }
+
+    #[test]
+    fn slash_after_a_value_is_a_division() {
+        assert_eq!(js("let r = f(a) / 2 / g"), "let r=f(a)/2/g");
+        assert_eq!(js("x = a[0] / b; y = 'q' / c"), "x=a[0]/b;y='q'/c");
+        assert_eq!(js("i++ / 2"), "i++/2");
+    }
+
+    #[test]
+    fn slash_after_an_operator_or_keyword_starts_a_regex() {
+        assert_eq!(js("x = /a b/;"), "x=/a b/;");
+        assert_eq!(js("f(/ /, / /)"), "f(/ /,/ /)");
+        assert_eq!(js("return /'//.source"), "return/'//.source");
+        assert_eq!(js("x = a ? /b c/ : / d /"), "x=a?/b c/:/ d /");
+        assert_eq!(js("} /* block */ /x y/.test(s)"), "}/x y/.test(s)");
+    }
+
+    #[test]
+    fn regex_classes_may_contain_slashes() {
+        assert_eq!(js("s.split(/[/ ]/)"), "s.split(/[/ ]/)");
+        assert_eq!(js("s.replace(/\\/ +/g, '')"), "s.replace(/\\/ +/g,'')");
+    }
+
+    #[test]
+    fn templates_are_copied_verbatim() {
+        assert_eq!(js("`a  ${ b + `c  ${d}` }  e`"), "`a  ${ b + `c  ${d}` }  e`");
+        assert_eq!(js("x = `one\n  two`;\ny()"), "x=`one\n  two`;y()");
+        assert_eq!(js("f(`${'}'}`) / 2"), "f(`${'}'}`)/2");
+    }
+
+    #[test]
+    fn line_breaks_that_may_end_a_statement_are_kept() {
+        assert_eq!(js("let a = 1\nlet b = 2"), "let a=1\nlet b=2");
+        assert_eq!(js("return\nx"), "return\nx");
+        assert_eq!(js("a++\nb"), "a++\nb");
+        assert_eq!(js("x\n++\ny"), "x\n++y");
+        assert_eq!(js("let o = {}\nf()"), "let o={}\nf()");
+        assert_eq!(js("x = function () {}\nfoo()"), "x=function(){}\nfoo()");
+        assert_eq!(js("const f = () => {\n}\nf()"), "const f=()=>{}\nf()");
+        assert_eq!(js("if (a) {\n  b()\n}\nc()"), "if(a){b()}\nc()");
+    }
+
+    #[test]
+    fn line_breaks_inside_a_statement_are_dropped() {
+        assert_eq!(js("foo(\n  a,\n  b\n)"), "foo(a,b)");
+        assert_eq!(js("f(function () {\n}\n, b)"), "f(function(){},b)");
+        assert_eq!(js("promise\n  .then(f)\n  .catch(g)"), "promise.then(f).catch(g)");
+        assert_eq!(js("let x =\n  a +\n  b"), "let x=a+b");
+    }
+
+    #[test]
+    fn unterminated_literals_are_errors() {
+        assert!(minify_js("app.js", "src/app.js", "let s = 'abc").is_err());
+        assert!(minify_js("app.js", "src/app.js", "let t = `abc").is_err());
+        assert!(minify_js("app.js", "src/app.js", "x = /abc").is_err());
+        assert!(minify_js("app.js", "src/app.js", "/* abc").is_err());
+    }
+
+    #[test]
+    fn js_source_map_points_at_the_source() {
+        let minified = minify_js("components/app.js", "src/app.js", "let x = 1\n\nx = 2").unwrap();
+        assert!(minified.code.ends_with("\n//# sourceMappingURL=app.js.map\n"));
+        let map: serde_json::Value = serde_json::from_str(&minified.source_map).unwrap();
+        assert_eq!(map["sources"][0], "src/app.js");
+        assert_eq!(map["sourcesContent"][0], "let x = 1\n\nx = 2");
+    }
+
+    #[test]
+    fn css_source_map_embeds_the_unscoped_source() {
+        let source = ".button { color: red; }\n";
+        let scoped = ".button[data-component=\"button\"] { color: red; }\n";
+        let minified = minify_css("components/button.css", "src/button.css", source, scoped).unwrap();
+        assert!(minified.code.starts_with(".button[data-component=button]{color:red}"));
+        assert!(minified.code.ends_with("\n/*# sourceMappingURL=button.css.map */\n"));
+        let map: serde_json::Value = serde_json::from_str(&minified.source_map).unwrap();
+        assert_eq!(map["sourcesContent"][0], source);
+    }
+}
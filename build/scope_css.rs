@@ -0,0 +1,361 @@
+// my_ssr_server/build/scope_css.rs
+// Scopes component stylesheets to their component, used by build.rs.
+//
+// `print_component_markup` puts `data-component="<component>"` on the root
+// element of every component (see `pages_components::component::SCOPE_ATTRIBUTE`).
+// Each selector of the component's stylesheet is rewritten so its subject (last)
+// compound only matches elements of the component: its root element or elements
+// inside it, but not inside another component rendered within it. With
+// `S = [data-component="button_component"]` and
+// `B = [data-component]:not(S)`:
+//
+//     body.dark .my-button:hover  ->  body.dark .my-button:hover:where(S, S :not(B)):where(:not(S B *))
+//
+// Ancestors in the selector are left alone, so `body.dark .my-button` still
+// follows a class set on the page. `:where()` adds no specificity, so the rules
+// keep their original priority. A component nested in a different component
+// nested in itself (`task_list` > `task_card` > `task_list`) loses the styles of
+// its inner instance.
+//
+// A selector whose subject is the document (`:root`, `html`, `body`) can't be
+// scoped and fails the build. Such rules belong in a global stylesheet, or in one
+// whose first comment is `/* @unscoped */`, which is left global.
+
+// Must match `pages_components::component::SCOPE_ATTRIBUTE`.
+const SCOPE_ATTRIBUTE: &str = "data-component";
+
+const UNSCOPED_MARKER: &str = "/* @unscoped */";
+
+// At-rules whose block holds ordinary style rules that need scoping too.
+const CONDITIONAL_AT_RULES: &[&str] = &["media", "supports", "container", "layer", "document"];
+
+// Pseudo-elements that may be written with a single colon.
+const LEGACY_PSEUDO_ELEMENTS: &[&str] = &[":before", ":after", ":first-line", ":first-letter"];
+
+// Subjects that match the document rather than anything a component renders.
+const DOCUMENT_SELECTORS: &[&str] = &[":root", "html", "body"];
+
+/// Whether the stylesheet opted out of scoping.
+pub fn is_unscoped(css: &str) -> bool {
+    css.trim_start().starts_with(UNSCOPED_MARKER)
+}
+
+/// Rewrites every selector of `css` to the component `scope` (e.g. "button_component").
+/// Comments and line breaks are kept, so line numbers still match the source.
+pub fn scope_css(css: &str, scope: &str) -> Result<String, String> {
+    let component = format!(r#"[{}="{}"]"#, SCOPE_ATTRIBUTE, scope);
+    let other_component = format!("[{}]:not({})", SCOPE_ATTRIBUTE, component);
+    let suffix = format!(
+        ":where({component}, {component} :not({other})):where(:not({component} {other} *))",
+        component = component,
+        other = other_component
+    );
+    let mut output = String::with_capacity(css.len() * 2);
+    scope_rules(css, &suffix, &mut output)?;
+    Ok(output)
+}
+
+/// Scopes a list of rules (a whole stylesheet or the body of `@media`).
+fn scope_rules(css: &str, suffix: &str, output: &mut String) -> Result<(), String> {
+    let mut position = 0;
+    while position < css.len() {
+        let rest = &css[position..];
+        let first = rest.as_bytes()[0];
+
+        if first.is_ascii_whitespace() {
+            let length = rest.len() - rest.trim_start().len();
+            output.push_str(&rest[..length]);
+            position += length;
+        } else if rest.starts_with("/*") {
+            let length = comment_length(rest)?;
+            output.push_str(&rest[..length]);
+            position += length;
+        } else if first == b'@' {
+            let name_length = rest[1..]
+                .find(|character: char| !(character.is_ascii_alphanumeric() || character == '-'))
+                .map_or(rest.len(), |length| length + 1);
+            let name = &rest[1..name_length];
+            let prelude_length = find_top_level(rest, b"{;")?.unwrap_or(rest.len());
+            if rest.as_bytes().get(prelude_length) != Some(&b'{') {
+                // `@import ...;`, `@charset ...;`
+                let length = (prelude_length + 1).min(rest.len());
+                output.push_str(&rest[..length]);
+                position += length;
+                continue;
+            }
+            let block_end = block_end(rest, prelude_length)?;
+            if CONDITIONAL_AT_RULES.contains(&name) {
+                output.push_str(&rest[..=prelude_length]);
+                scope_rules(&rest[prelude_length + 1..block_end], suffix, output)?;
+                output.push('}');
+            } else {
+                // `@keyframes`, `@font-face`, ...: nothing to scope.
+                output.push_str(&rest[..=block_end]);
+            }
+            position += block_end + 1;
+        } else {
+            let prelude_length = find_top_level(rest, b"{")?
+                .ok_or_else(|| format!("expected `{{` after selector {:?}", rest.trim()))?;
+            let block_end = block_end(rest, prelude_length)?;
+            output.push_str(&scope_selector_list(&rest[..prelude_length], suffix)?);
+            output.push_str(&rest[prelude_length..=block_end]);
+            position += block_end + 1;
+        }
+    }
+    Ok(())
+}
+
+fn scope_selector_list(selectors: &str, suffix: &str) -> Result<String, String> {
+    let mut scoped = Vec::new();
+    let mut rest = selectors;
+    loop {
+        let end = find_top_level(rest, b",")?.unwrap_or(rest.len());
+        scoped.push(scope_selector(&rest[..end], suffix)?);
+        if end == rest.len() {
+            break;
+        }
+        rest = &rest[end + 1..];
+    }
+    Ok(scoped.join(","))
+}
+
+/// Inserts `suffix` at the end of the subject (last) compound selector, before
+/// any pseudo-element.
+fn scope_selector(selector: &str, suffix: &str) -> Result<String, String> {
+    let end = selector.trim_end().len();
+    let bytes = selector.as_bytes();
+
+    // The subject starts after the last combinator outside parentheses.
+    let mut subject_start = selector.len() - selector.trim_start().len();
+    let mut depth = 0;
+    let mut position = subject_start;
+    while position < end {
+        match bytes[position] {
+            b'(' | b'[' => depth += 1,
+            b')' | b']' => depth -= 1,
+            b'"' | b'\'' => {
+                position += string_length(&selector[position..])?;
+                continue;
+            }
+            b'\\' => position += 1,
+            byte if depth == 0 && (byte.is_ascii_whitespace() || matches!(byte, b'>' | b'+' | b'~')) => {
+                subject_start = position + 1;
+            }
+            _ => {}
+        }
+        position += 1;
+    }
+
+    let subject = &selector[subject_start..end];
+    if subject.is_empty() {
+        return Err(format!("selector {:?} has no subject", selector.trim()));
+    }
+    let mut insert_at = end;
+    let mut depth = 0;
+    let mut position = 0;
+    while position < subject.len() {
+        let rest = &subject[position..];
+        match rest.as_bytes()[0] {
+            b'(' | b'[' => depth += 1,
+            b')' | b']' => depth -= 1,
+            b'"' | b'\'' => {
+                position += string_length(rest)?;
+                continue;
+            }
+            b'\\' => position += 1,
+            b':' if depth == 0
+                && (rest.starts_with("::") || LEGACY_PSEUDO_ELEMENTS.iter().any(|name| starts_with_name(rest, name))) =>
+            {
+                insert_at = subject_start + position;
+                break;
+            }
+            b':' if depth == 0 && starts_with_name(rest, ":root") => {
+                return Err(document_subject_error(selector));
+            }
+            _ => {}
+        }
+        position += 1;
+    }
+    if DOCUMENT_SELECTORS.iter().any(|name| starts_with_name(subject, name)) {
+        return Err(document_subject_error(selector));
+    }
+
+    Ok(format!("{}{}{}", &selector[..insert_at], suffix, &selector[insert_at..]))
+}
+
+/// Whether `css` starts with the identifier `name` (`body` but not `bodyguard`).
+fn starts_with_name(css: &str, name: &str) -> bool {
+    css.len() >= name.len()
+        && css[..name.len()].eq_ignore_ascii_case(name)
+        && !css[name.len()..]
+            .starts_with(|character: char| character.is_ascii_alphanumeric() || character == '-' || character == '_')
+}
+
+fn document_subject_error(selector: &str) -> String {
+    format!(
+        "selector {:?} targets the document, which a component stylesheet can't scope; \
+         move it to a global stylesheet or start the file with `/* @unscoped */`",
+        selector.trim()
+    )
+}
+
+/// Byte offset of the first `targets` byte outside strings, comments, parentheses and brackets.
+fn find_top_level(css: &str, targets: &[u8]) -> Result<Option<usize>, String> {
+    let bytes = css.as_bytes();
+    let mut depth = 0;
+    let mut position = 0;
+    while position < bytes.len() {
+        match bytes[position] {
+            b'"' | b'\'' => {
+                position += string_length(&css[position..])?;
+                continue;
+            }
+            b'/' if bytes.get(position + 1) == Some(&b'*') => {
+                position += comment_length(&css[position..])?;
+                continue;
+            }
+            b'\\' => position += 1,
+            b'(' | b'[' => depth += 1,
+            b')' | b']' => depth -= 1,
+            byte if depth == 0 && targets.contains(&byte) => return Ok(Some(position)),
+            _ => {}
+        }
+        position += 1;
+    }
+    Ok(None)
+}
+
+/// Offset of the `}` closing the block opened at `open`.
+fn block_end(css: &str, open: usize) -> Result<usize, String> {
+    let mut depth = 0;
+    let mut position = open;
+    let bytes = css.as_bytes();
+    while position < bytes.len() {
+        match bytes[position] {
+            b'"' | b'\'' => {
+                position += string_length(&css[position..])?;
+                continue;
+            }
+            b'/' if bytes.get(position + 1) == Some(&b'*') => {
+                position += comment_length(&css[position..])?;
+                continue;
+            }
+            b'\\' => position += 1,
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(position);
+                }
+            }
+            _ => {}
+        }
+        position += 1;
+    }
+    Err(format!("unclosed block: {:?}", css[..open].trim()))
+}
+
+fn comment_length(css: &str) -> Result<usize, String> {
+    css[2..]
+        .find("*/")
+        .map(|end| end + 4)
+        .ok_or_else(|| "unterminated comment".to_string())
+}
+
+fn string_length(css: &str) -> Result<usize, String> {
+    let bytes = css.as_bytes();
+    let quote = bytes[0];
+    let mut position = 1;
+    while position < bytes.len() {
+        match bytes[position] {
+            b'\\' => position += 2,
+            byte if byte == quote => return Ok(position + 1),
+            _ => position += 1,
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const S: &str = r#"[data-component="card"]"#;
+
+    fn suffix() -> String {
+        format!(
+            ":where({s}, {s} :not([data-component]:not({s}))):where(:not({s} [data-component]:not({s}) *))",
+            s = S
+        )
+    }
+
+    fn scoped(css: &str) -> String {
+        scope_css(css, "card").unwrap()
+    }
+
+    #[test]
+    fn the_subject_compound_is_scoped() {
+        assert_eq!(scoped(".title { color: red }"), format!(".title{} {{ color: red }}", suffix()));
+        assert_eq!(scoped(".card .title{}"), format!(".card .title{}{{}}", suffix()));
+        assert_eq!(scoped("ul > li + li{}"), format!("ul > li + li{}{{}}", suffix()));
+    }
+
+    #[test]
+    fn ancestors_outside_the_component_still_match() {
+        assert_eq!(scoped("body.dark .button {}"), format!("body.dark .button{} {{}}", suffix()));
+        assert_eq!(scoped(":root[data-theme] a{}"), format!(":root[data-theme] a{}{{}}", suffix()));
+    }
+
+    #[test]
+    fn pseudo_classes_stay_before_the_suffix_and_pseudo_elements_after() {
+        assert_eq!(scoped("a:hover{}"), format!("a:hover{}{{}}", suffix()));
+        assert_eq!(scoped("a:not(.x, .y > .z){}"), format!("a:not(.x, .y > .z){}{{}}", suffix()));
+        assert_eq!(scoped("a::before{}"), format!("a{}::before{{}}", suffix()));
+        assert_eq!(scoped("p:first-line{}"), format!("p{}:first-line{{}}", suffix()));
+        assert_eq!(scoped("p:first-of-type{}"), format!("p:first-of-type{}{{}}", suffix()));
+    }
+
+    #[test]
+    fn every_selector_of_a_list_is_scoped() {
+        assert_eq!(scoped("a,\nb > c {}"), format!("a{s},\nb > c{s} {{}}", s = suffix()));
+    }
+
+    #[test]
+    fn nested_rules_are_scoped() {
+        assert_eq!(
+            scoped("@media (min-width: 10px) { a {} }\n@keyframes k { from {} }"),
+            format!("@media (min-width: 10px) {{ a{} {{}} }}\n@keyframes k {{ from {{}} }}", suffix())
+        );
+    }
+
+    #[test]
+    fn child_components_are_excluded() {
+        // The suffix stops at the root of any other component rendered inside this one.
+        assert!(suffix().contains(r#":where(:not([data-component="card"] [data-component]:not([data-component="card"]) *))"#));
+        assert!(suffix().contains(r#"[data-component="card"] :not([data-component]:not([data-component="card"]))"#));
+    }
+
+    #[test]
+    fn document_subjects_are_rejected() {
+        for css in [":root { --x: 1 }", "html {}", "body.dark {}", ".x, BODY {}"] {
+            let error = scope_css(css, "card").unwrap_err();
+            assert!(error.contains("@unscoped"), "{}", error);
+        }
+        // Only whole names count.
+        assert!(scope_css("bodyguard {}", "card").is_ok());
+    }
+
+    #[test]
+    fn unscoped_stylesheets_are_detected() {
+        assert!(is_unscoped("\n/* @unscoped */\nbody {}"));
+        assert!(!is_unscoped("a {} /* @unscoped */"));
+    }
+
+    #[test]
+    fn comments_strings_and_line_breaks_are_kept() {
+        assert_eq!(
+            scoped("/* a { } */\na[title=\"x, y\"]\n{ content: \"}\" }"),
+            format!("/* a {{ }} */\na[title=\"x, y\"]{}\n{{ content: \"}}\" }}", suffix())
+        );
+    }
+}
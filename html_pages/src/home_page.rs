@@ -1,85 +1,142 @@
-use super::page_template::{PAGE_BOTTOM, PAGE_CONTENT, PAGE_TOP};
-use pages_components::ButtonComponent;
-use pages_components::TaskCard001;
+use super::page::{Page, PageRequest, Site};
+use super::page_meta::PageMeta;
+use super::page_template::{DEFAULT_CRITICAL_CSS_BUDGET, GLOBAL_STYLES, Layout};
+use pages_components::{
+    AssetCollector, AssetScope, ButtonComponent, ButtonComponentProps, ChecklistItem, Component,
+    IdAllocator, TaskCard001, TaskCard001Props, TaskList001, TaskList001Props,
+};
 
+// Files used by the home page itself (served from html_pages/static).
+const PAGE_STYLES: &[&str] = &["home_page.css"];
+const PAGE_BOTTOM_SCRIPTS: &[&str] = &["home_page.js"];
+
+/// Head metadata for the home page.
+pub fn page_meta() -> PageMeta {
+    PageMeta {
+        title: String::from("Eduardo Osteicoechea"),
+        description: Some(String::from(
+            "Personal site of Eduardo Osteicoechea, server-rendered with Rust.",
+        )),
+        canonical_url: Some(String::from("/")),
+        image_url: Some(String::from("/personal_photo_white_720x720.webp")),
+        site_name: Some(String::from("Eduardo Osteicoechea")),
+        ..PageMeta::default()
+    }
+}
+
+/// `ids` is the allocator the components were built with.
 pub fn print_page(
+    site: &Site,
     page_level_data: &[String],
-    css_files_markup: &[String],
-    components_markup: &[String],
-    js_files_markup: &[String],
+    components: &[&dyn Component],
+    ids: IdAllocator,
 ) -> String {
-    let button = ButtonComponent::new(
-        vec![String::from("button_component.css")],
-        vec![String::from("button_component.js")],
-        Some(vec![String::from("button_component_head.js")]),
-        None,
-        None,
-    );
-
-    let task_card: TaskCard001 = TaskCard001::new(
-      vec![String::from("task_card_001.css")],
-      vec![String::from("task_card_001.js")],
-      None,
-      None,
-      None,
-    );
+    // Register global, page and component assets; the collector drops duplicates
+    // and keeps the global -> page -> component order.
+    let mut assets = AssetCollector::new(site.assets.clone());
+    for file_name in GLOBAL_STYLES {
+        assets.add_style(AssetScope::Global, file_name);
+    }
+    for file_name in PAGE_STYLES {
+        assets.add_style(AssetScope::Page, file_name);
+    }
+    for file_name in PAGE_BOTTOM_SCRIPTS {
+        assets.add_bottom_script(AssetScope::Page, file_name);
+    }
 
-    // Get the component's markup and its associated head/bottom JS/CSS
-    let button_markup = button.print_component_markup();
-    let button_styles = button.print_styles_head_tags();
-    let button_head_js = button.print_javascript_head_tags();
-    let button_bottom_js = button.print_javascript_bottom_tags();
+    // Collect every component's markup while letting it register its assets
+    let mut components_markup: Vec<String> = Vec::new();
+    for component in components {
+        component.register_assets(&mut assets);
+        components_markup.push(component.print_component_markup());
+    }
 
-    // Combine all CSS markup (global + component)
-    let all_css_markup = format!("{}\n{}", css_files_markup.join("\n    "), button_styles);
+    let main = format!(
+        "<h2>Page Content Here</h2>\n{}\n{}",
+        page_level_data.join("\n"),
+        components_markup.join("\n")
+    );
 
-    // Combine all JS head markup (if any)
-    let all_head_js_markup = button_head_js; // For now, only button_component_head.js
+    Layout {
+        meta: page_meta(),
+        base_url: site.base_url.clone(),
+        assets,
+        critical_css_budget: Some(DEFAULT_CRITICAL_CSS_BUDGET),
+        ids,
+        main,
+        ..Layout::default()
+    }
+    .render()
+}
 
-    // Combine all JS bottom markup (global + component)
-    let all_bottom_js_markup = format!("{}\n{}", js_files_markup.join("\n    "), button_bottom_js);
+/// The site's landing page, served at `/`.
+pub struct HomePage;
 
-    // Combine all component markup
-    let all_components_markup = format!("{}\n{}", components_markup.join("\n"), button_markup);
+impl Page for HomePage {
+    fn path(&self) -> &'static str {
+        "/"
+    }
 
+    fn render(&self, request: &PageRequest) -> String {
+        let page_level_data = vec![
+            String::from("<p>This is dynamic page-level data.</p>"),
+            String::from("<p>More data here!</p>"),
+        ];
 
-    let page_data_joined = page_level_data.join("\n");
+        // One allocator per render: the same components always get the same ids.
+        let mut ids = IdAllocator::new();
 
-    // Step 1: Format PAGE_TOP and PAGE_BOTTOM by filling their internal placeholders.
-    let page_top_with_css_and_head_js = format!(
-        "{}\n{}\n{}\n",
-        PAGE_TOP,
-        css_files_markup = all_css_markup,
-        js_files_markup = all_head_js_markup // Assuming PAGE_TOP has a placeholder for head JS
-    );
+        let button = ButtonComponent::new(ButtonComponentProps::default(), &mut ids);
 
-    let page_bottom_with_js = format!(
-        "{}\n{}\n",
-        PAGE_BOTTOM,
-        js_files_markup = all_bottom_js_markup
-    );
+        let task_card = TaskCard001::new(
+            TaskCard001Props {
+                title: String::from("Write the component props"),
+                body: String::from("Replace the opaque data strings with typed, deserializable props."),
+                due: Some(String::from("2025-07-10")),
+                done: false,
+                checklist: vec![
+                    ChecklistItem {
+                        text: String::from("Define the props structs"),
+                        done: true,
+                    },
+                    ChecklistItem {
+                        text: String::from("Deserialize them from the page data"),
+                        done: false,
+                    },
+                ],
+            },
+            &mut ids,
+        )
+        .with_footer(ButtonComponent::new(
+            ButtonComponentProps {
+                label: String::from("Mark as done"),
+            },
+            &mut ids,
+        ));
 
-    let task_card_markup = task_card.print_component_markup();
+        let task_list = TaskList001::new(
+            TaskList001Props {
+                title: String::from("Up next"),
+                tasks: vec![
+                    TaskCard001Props {
+                        title: String::from("Add template blocks"),
+                        body: String::from("Support if/else and for blocks in component templates."),
+                        done: true,
+                        ..TaskCard001Props::default()
+                    },
+                    TaskCard001Props {
+                        title: String::from("Include components by name"),
+                        body: String::from("Render task cards from the task list template."),
+                        due: Some(String::from("2025-07-20")),
+                        ..TaskCard001Props::default()
+                    },
+                ],
+            },
+            &mut ids,
+        );
 
-    // Step 2: Format PAGE_CONTENT with its specific variables.
-    let page_content_with_data_components = format!(
-        "
-        {}\n
-        {}\n
-        {}\n
-        {}\n
-        ",
-        PAGE_CONTENT,
-        page_level_data = page_data_joined,
-        components_markup = all_components_markup,
-        task_card_markup = task_card_markup
-    );
+        let components: Vec<&dyn Component> = vec![&button, &task_card, &task_list];
 
-    // Step 3: Combine all the already formatted parts.
-    format!(
-        "{}\n{}\n{}",
-        page_top_with_css_and_head_js,
-        page_content_with_data_components,
-        page_bottom_with_js
-    )
-}
\ No newline at end of file
+        print_page(&request.site, &page_level_data, &components, ids)
+    }
+}
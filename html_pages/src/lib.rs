@@ -4,10 +4,14 @@
 // `pub mod` makes the module public, so it can be accessed from other crates.
 pub mod page_template;
 
+// Declare the `page_meta` module (per-page <title>, description, Open Graph...).
+pub mod page_meta;
+
 // Declare the `home_page` module.
 pub mod home_page;
 
-// You can also re-export items for easier access from the consuming crate.
-// For example, `pub use home_page::print_page;` would allow `use html_pages::print_page;`
-// in `main.rs` instead of `use html_pages::home_page::print_page;`.
-// For now, we'll keep it explicit for learning.
\ No newline at end of file
+// Declare the `page` module: the `Page` trait and the registry that mounts pages on the router.
+pub mod page;
+
+// Re-export the page registry so `main.rs` can simply call `html_pages::registry()`.
+pub use page::{registry, Page, PageRegistry, PageRequest, Site};
\ No newline at end of file
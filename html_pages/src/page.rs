@@ -0,0 +1,140 @@
+// html_pages/src/page.rs
+
+// Every page of the site implements `Page`: it declares the route it answers,
+// the path parameters that route captures, and how to render itself.
+// `PageRegistry` holds all pages and mounts them onto an axum `Router`, so a
+// new page only has to be added to `registry()` below. The server hands the
+// registry the `Site` settings, which every request carries to its page.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::extract::{Path, Query};
+use axum::response::Html;
+use axum::routing::get;
+use axum::Router;
+use pages_components::AssetContext;
+
+use crate::home_page::HomePage;
+
+/// Settings shared by every page of the site, resolved from the server config.
+#[derive(Debug, Clone, Default)]
+pub struct Site {
+    /// Public origin of the site (e.g. "https://example.com"), used to make the
+    /// canonical and Open Graph URLs absolute. `None` leaves them relative.
+    pub base_url: Option<String>,
+    /// Resolves, inlines and bundles the stylesheets and scripts of the pages.
+    pub assets: AssetContext,
+}
+
+/// The data a page receives for one request.
+#[derive(Debug, Clone, Default)]
+pub struct PageRequest {
+    /// Path parameters captured by the route (e.g. `id` for `/tasks/:id`).
+    pub params: HashMap<String, String>,
+    /// Query string parameters.
+    pub query: HashMap<String, String>,
+    /// The site the page is rendered for.
+    pub site: Arc<Site>,
+}
+
+impl PageRequest {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+}
+
+/// A server-rendered page.
+pub trait Page: Send + Sync + 'static {
+    /// The route, in axum syntax (e.g. "/" or "/tasks/:id").
+    fn path(&self) -> &'static str;
+
+    /// Names of the path parameters captured by `path`, checked when mounting.
+    fn params(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Renders the full HTML document.
+    fn render(&self, request: &PageRequest) -> String;
+}
+
+/// The list of pages served by the application.
+#[derive(Clone, Default)]
+pub struct PageRegistry {
+    pages: Vec<Arc<dyn Page>>,
+}
+
+impl PageRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a page, builder style.
+    pub fn register(mut self, page: impl Page) -> Self {
+        self.pages.push(Arc::new(page));
+        self
+    }
+
+    pub fn pages(&self) -> &[Arc<dyn Page>] {
+        &self.pages
+    }
+
+    /// Adds one `GET` route per registered page to `router`; every page is
+    /// rendered for `site`.
+    ///
+    /// Panics if a page's declared `params` don't match the parameters in its
+    /// `path`, so the mistake shows up at startup rather than on first request.
+    pub fn mount<S>(&self, mut router: Router<S>, site: Site) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let site = Arc::new(site);
+        for page in &self.pages {
+            check_params(page.as_ref());
+
+            let page = Arc::clone(page);
+            let site = Arc::clone(&site);
+            router = router.route(
+                page.path(),
+                get(
+                    move |params: Option<Path<HashMap<String, String>>>,
+                          Query(query): Query<HashMap<String, String>>| async move {
+                        tracing::info!("Received request for {}", page.path());
+
+                        let request = PageRequest {
+                            params: params.map(|Path(params)| params).unwrap_or_default(),
+                            query,
+                            site,
+                        };
+                        Html(page.render(&request))
+                    },
+                ),
+            );
+        }
+        router
+    }
+}
+
+/// All pages of the site. Register new pages here.
+pub fn registry() -> PageRegistry {
+    PageRegistry::new().register(HomePage)
+}
+
+fn check_params(page: &dyn Page) {
+    let mut in_path: Vec<&str> = page
+        .path()
+        .split('/')
+        .filter_map(|segment| segment.strip_prefix(':').or_else(|| segment.strip_prefix('*')))
+        .collect();
+    let mut declared: Vec<&str> = page.params().to_vec();
+    in_path.sort_unstable();
+    declared.sort_unstable();
+
+    assert!(
+        in_path == declared,
+        "page `{}` declares params {:?} but its path captures {:?}",
+        page.path(),
+        declared,
+        in_path
+    );
+}
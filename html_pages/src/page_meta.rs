@@ -0,0 +1,160 @@
+// html_pages/src/page_meta.rs
+
+// Head metadata declared by each page: title, description, canonical URL,
+// Open Graph / Twitter card fields and robots directives. The layout renders
+// it into the `<head>`; every value is HTML-escaped.
+
+use pages_components::template::escape_html;
+
+/// How search engines may treat the page (`<meta name="robots">`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Robots {
+    /// `index` / `noindex`
+    pub index: bool,
+    /// `follow` / `nofollow`
+    pub follow: bool,
+}
+
+impl Default for Robots {
+    fn default() -> Self {
+        Self { index: true, follow: true }
+    }
+}
+
+impl Robots {
+    pub fn directives(&self) -> String {
+        format!(
+            "{}, {}",
+            if self.index { "index" } else { "noindex" },
+            if self.follow { "follow" } else { "nofollow" }
+        )
+    }
+}
+
+/// The Twitter card layout (`twitter:card`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TwitterCard {
+    #[default]
+    Summary,
+    SummaryLargeImage,
+}
+
+impl TwitterCard {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TwitterCard::Summary => "summary",
+            TwitterCard::SummaryLargeImage => "summary_large_image",
+        }
+    }
+}
+
+/// Head metadata for one page.
+///
+/// Open Graph and Twitter titles/descriptions reuse `title` and `description`.
+/// URLs may be absolute or site-relative (`/about`); relative ones are joined
+/// with the base URL given to `print_head_tags`, since Open Graph requires
+/// absolute URLs.
+#[derive(Debug, Clone)]
+pub struct PageMeta {
+    /// Text of the `<title>` element, also used for `og:title`.
+    pub title: String,
+    /// `<meta name="description">`, also used for `og:description`.
+    pub description: Option<String>,
+    /// `<link rel="canonical">`, also used for `og:url`.
+    pub canonical_url: Option<String>,
+    /// `og:type` (e.g. "website", "article").
+    pub og_type: String,
+    /// `og:image` / `twitter:image`.
+    pub image_url: Option<String>,
+    /// `og:site_name`.
+    pub site_name: Option<String>,
+    /// `twitter:card`.
+    pub twitter_card: TwitterCard,
+    /// `twitter:site` handle (e.g. "@someone").
+    pub twitter_site: Option<String>,
+    /// `<meta name="robots">`.
+    pub robots: Robots,
+}
+
+impl Default for PageMeta {
+    fn default() -> Self {
+        Self {
+            title: String::from("Rust SSR Application"),
+            description: None,
+            canonical_url: None,
+            og_type: String::from("website"),
+            image_url: None,
+            site_name: None,
+            twitter_card: TwitterCard::default(),
+            twitter_site: None,
+            robots: Robots::default(),
+        }
+    }
+}
+
+impl PageMeta {
+    /// Generates the `<meta>`/`<link>` tags for the `<head>` (not the `<title>`).
+    pub fn print_head_tags(&self, base_url: Option<&str>) -> String {
+        let canonical_url = self.canonical_url.as_deref().map(|url| absolute_url(url, base_url));
+        let image_url = self.image_url.as_deref().map(|url| absolute_url(url, base_url));
+
+        let mut tags: Vec<String> = Vec::new();
+
+        if let Some(description) = &self.description {
+            tags.push(meta_name("description", description));
+        }
+        tags.push(meta_name("robots", &self.robots.directives()));
+        if let Some(url) = &canonical_url {
+            tags.push(format!(r#"<link rel="canonical" href="{}">"#, escape_html(url)));
+        }
+
+        // Open Graph
+        tags.push(meta_property("og:title", &self.title));
+        tags.push(meta_property("og:type", &self.og_type));
+        if let Some(description) = &self.description {
+            tags.push(meta_property("og:description", description));
+        }
+        if let Some(url) = &canonical_url {
+            tags.push(meta_property("og:url", url));
+        }
+        if let Some(url) = &image_url {
+            tags.push(meta_property("og:image", url));
+        }
+        if let Some(site_name) = &self.site_name {
+            tags.push(meta_property("og:site_name", site_name));
+        }
+
+        // Twitter card
+        tags.push(meta_name("twitter:card", self.twitter_card.as_str()));
+        tags.push(meta_name("twitter:title", &self.title));
+        if let Some(description) = &self.description {
+            tags.push(meta_name("twitter:description", description));
+        }
+        if let Some(url) = &image_url {
+            tags.push(meta_name("twitter:image", url));
+        }
+        if let Some(site) = &self.twitter_site {
+            tags.push(meta_name("twitter:site", site));
+        }
+
+        tags.join("\n")
+    }
+}
+
+fn meta_name(name: &str, content: &str) -> String {
+    format!(r#"<meta name="{}" content="{}">"#, name, escape_html(content))
+}
+
+fn meta_property(property: &str, content: &str) -> String {
+    format!(r#"<meta property="{}" content="{}">"#, property, escape_html(content))
+}
+
+/// Joins a site-relative URL with `base_url`; absolute URLs are returned as is.
+fn absolute_url(url: &str, base_url: Option<&str>) -> String {
+    match base_url {
+        Some(base_url) if url.starts_with('/') => {
+            format!("{}{}", base_url.trim_end_matches('/'), url)
+        }
+        _ => url.to_string(),
+    }
+}
@@ -1,35 +1,118 @@
 // html_pages/src/page_template.rs
 
-// These are constants holding parts of your HTML template.
-// 'static lifetime means they are valid for the entire duration of the program.
-// They are string slices (&str) which are efficient for static content.
-
-// PAGE_TOP: Contains the DOCTYPE, html, head, and opening body tag.
-// It includes a placeholder for CSS files.
-pub const PAGE_TOP: &str = r#"
-<!DOCTYPE html>
+// The HTML skeleton shared by every page, with named slots that pages fill.
+// Slots are rendered with the same placeholder engine as the components:
+// `{{title}}` is HTML-escaped, the `{{{...}}}` slots receive markup as is.
+
+use pages_components::template::{self, Context};
+use pages_components::{AssetCollector, IdAllocator};
+
+use crate::page_meta::PageMeta;
+
+// GLOBAL_STYLES: Stylesheets linked on every page, ahead of page and component styles.
+pub const GLOBAL_STYLES: &[&str] = &["global.css"];
+
+// DEFAULT_CRITICAL_CSS_BUDGET: Bytes of CSS worth inlining; about what fits in
+// the first round trip of a new connection together with the markup.
+pub const DEFAULT_CRITICAL_CSS_BUDGET: usize = 14 * 1024;
+
+// DEFAULT_HEADER: Header markup used when a page doesn't provide its own.
+pub const DEFAULT_HEADER: &str = "<h1>Welcome to the Modular Rust SSR App!</h1>";
+
+// PAGE_LAYOUT: The complete document. Every slot appears exactly once.
+pub const PAGE_LAYOUT: &str = r#"<!DOCTYPE html>
 <html lang="en">
 <head>
     <meta charset="UTF-8">
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>Rust SSR Application</title>
+    <title>{{title}}</title>
+{{{meta_tags}}}
+{{{head_assets}}}
 </head>
 <body>
-"#;
-
-// PAGE_CONTENT: This is where the main content of your specific page will go.
-// It includes placeholders for page-level data and components.
-pub const PAGE_CONTENT: &str = r#"
     <header>
-        <h1>Welcome to the Modular Rust SSR App!</h1>
+{{{header}}}
     </header>
     <main>
-        <h2>Page Content Here</h2>
+{{{main}}}
     </main>
-"#;
-
-// PAGE_BOTTOM: Contains the closing body and html tags, and a placeholder for JS files.
-pub const PAGE_BOTTOM: &str = r#"
+    <footer>
+{{{footer}}}
+    </footer>
+{{{bottom_scripts}}}
 </body>
 </html>
-"#;
\ No newline at end of file
+"#;
+
+/// The named slots of `PAGE_LAYOUT`.
+///
+/// `meta` is rendered (escaped) into the `<title>` and `<meta>` tags and
+/// `assets` into the `<link>`/`<style>`/`<script>` tags; every other slot holds
+/// markup that is inserted without escaping, so only put trusted (already
+/// rendered) HTML there.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    /// Title, description, canonical URL, Open Graph, robots...
+    pub meta: PageMeta,
+    /// Public origin of the site (e.g. "https://example.com") used to make the
+    /// canonical and Open Graph URLs absolute.
+    pub base_url: Option<String>,
+    /// Stylesheets and scripts registered by the page and its components,
+    /// collected with the site's `AssetContext` (see `Site::assets`).
+    pub assets: AssetCollector,
+    /// When set, stylesheets are inlined into a `<style>` block up to this many
+    /// bytes and the rest load asynchronously instead of blocking the first paint.
+    pub critical_css_budget: Option<usize>,
+    /// Ids handed to the rendered components, printed as JSON for their scripts.
+    pub ids: IdAllocator,
+    /// Content of the `<header>` element.
+    pub header: String,
+    /// Content of the `<main>` element.
+    pub main: String,
+    /// Content of the `<footer>` element.
+    pub footer: String,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            meta: PageMeta::default(),
+            base_url: None,
+            assets: AssetCollector::default(),
+            critical_css_budget: None,
+            ids: IdAllocator::new(),
+            header: String::from(DEFAULT_HEADER),
+            main: String::new(),
+            footer: String::new(),
+        }
+    }
+}
+
+impl Layout {
+    /// Fills every slot of `PAGE_LAYOUT` and returns the full document.
+    pub fn render(&self) -> String {
+        let styles = match self.critical_css_budget {
+            Some(budget) => self.assets.print_styles_head_tags_inlined(budget),
+            None => self.assets.print_styles_head_tags(),
+        };
+        let head_assets = format!("{}\n{}", styles, self.assets.print_javascript_head_tags());
+        // The instances JSON comes first so component scripts can read it right away.
+        let bottom_scripts = format!(
+            "{}\n{}",
+            self.ids.print_instances_script(),
+            self.assets.print_javascript_bottom_tags()
+        );
+
+        let context = Context::new()
+            .with("title", &self.meta.title)
+            .with("meta_tags", self.meta.print_head_tags(self.base_url.as_deref()))
+            .with("head_assets", head_assets)
+            .with("header", &self.header)
+            .with("main", &self.main)
+            .with("footer", &self.footer)
+            .with("bottom_scripts", bottom_scripts);
+
+        template::render(PAGE_LAYOUT, &context)
+            .expect("PAGE_LAYOUT should render with every slot filled")
+    }
+}
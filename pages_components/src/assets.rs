@@ -0,0 +1,368 @@
+// pages_components/src/assets.rs
+
+// Collects the CSS and JS files a page needs while it is being rendered.
+//
+// The page registers its global and page-level files, then every rendered
+// component registers its own. Each file is emitted once, no matter how many
+// component instances asked for it, and tags always come out in the same
+// order: global files first, then page files, then component files, each group
+// in registration order.
+//
+// The collector is built with the server's `AssetContext`:
+//
+// - File names are logical (`button_component.css`); the URLs in the tags go
+//   through its `AssetManifest`, which maps them to the content-hashed names
+//   produced by the build (`button_component.3f9a1c2b.css`).
+// - With an `AssetBundler`, each tag group with more than one file (styles,
+//   head scripts, bottom scripts) is emitted as a single tag for a bundle of
+//   those files instead.
+// - With an `AssetReader`, `print_styles_head_tags_inlined` copies the leading
+//   stylesheets into a `<style>` block and loads the remaining ones without
+//   blocking the first paint.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Maps logical asset names to their fingerprinted file names.
+#[derive(Debug, Clone, Default)]
+pub struct AssetManifest {
+    entries: HashMap<String, String>,
+}
+
+impl AssetManifest {
+    /// Parses the `{"logical": "fingerprinted"}` JSON written by the build script.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        Ok(Self {
+            entries: serde_json::from_str(json)?,
+        })
+    }
+
+    /// The fingerprinted name for `file_name`, if the manifest knows it.
+    pub fn get(&self, file_name: &str) -> Option<&str> {
+        self.entries.get(file_name).map(String::as_str)
+    }
+
+    /// Every fingerprinted file name in the manifest.
+    pub fn fingerprinted_names(&self) -> impl Iterator<Item = &str> {
+        self.entries.values().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Gives access to the contents of the staged assets.
+pub trait AssetReader: Send + Sync {
+    /// The served contents of `file_name` (a logical name), if it exists.
+    fn read(&self, file_name: &str) -> Option<Arc<str>>;
+}
+
+/// `code` without its `sourceMappingURL` comments, whose relative URLs would
+/// resolve against the wrong folder once the code is inlined or bundled.
+pub fn strip_source_map_comments(code: &str) -> String {
+    code.lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            !line.starts_with("//# sourceMappingURL=") && !line.starts_with("/*# sourceMappingURL=")
+        })
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+/// Stylesheet or script, the two kinds of files a bundle can hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AssetKind {
+    Style,
+    Script,
+}
+
+/// Joins the files of one tag group into a single served file.
+pub trait AssetBundler: Send + Sync {
+    /// The URL of a bundle holding `file_names` (logical names, in this order),
+    /// or `None` to fall back to one tag per file.
+    fn bundle_url(&self, kind: AssetKind, file_names: &[&str]) -> Option<String>;
+}
+
+/// The manifest, reader and bundler the server renders pages with.
+///
+/// Cloning is cheap; every page render gets its own copy in its `AssetCollector`.
+/// The default has an empty manifest, no reader and no bundler: one tag per
+/// file under its logical name, and nothing inlined.
+#[derive(Clone, Default)]
+pub struct AssetContext {
+    manifest: Arc<AssetManifest>,
+    reader: Option<Arc<dyn AssetReader>>,
+    bundler: Option<Arc<dyn AssetBundler>>,
+}
+
+impl AssetContext {
+    pub fn new(manifest: Arc<AssetManifest>) -> Self {
+        Self {
+            manifest,
+            ..Self::default()
+        }
+    }
+
+    /// Reads the assets to inline and bundle, builder style.
+    pub fn with_reader(mut self, reader: Arc<dyn AssetReader>) -> Self {
+        self.reader = Some(reader);
+        self
+    }
+
+    /// Turns on bundling, builder style.
+    pub fn with_bundler(mut self, bundler: Arc<dyn AssetBundler>) -> Self {
+        self.bundler = Some(bundler);
+        self
+    }
+
+    pub fn manifest(&self) -> &AssetManifest {
+        &self.manifest
+    }
+
+    /// The URL path for a logical asset name: `/` + its fingerprinted name when
+    /// the manifest knows it, `/` + the name itself otherwise.
+    pub fn url(&self, file_name: &str) -> String {
+        format!("/{}", self.manifest.get(file_name).unwrap_or(file_name))
+    }
+
+    /// The contents of a staged asset, if there is a reader and it has the file.
+    pub fn read(&self, file_name: &str) -> Option<Arc<str>> {
+        self.reader.as_ref().and_then(|reader| reader.read(file_name))
+    }
+
+    /// One URL per file, or the URL of their bundle when bundling is on.
+    fn group_urls(&self, kind: AssetKind, file_names: &[&str]) -> Vec<String> {
+        if file_names.len() > 1
+            && let Some(url) = self.bundler.as_ref().and_then(|bundler| bundler.bundle_url(kind, file_names))
+        {
+            return vec![url];
+        }
+        file_names.iter().map(|file_name| self.url(file_name)).collect()
+    }
+}
+
+impl fmt::Debug for AssetContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AssetContext")
+            .field("manifest", &self.manifest)
+            .field("reader", &self.reader.is_some())
+            .field("bundler", &self.bundler.is_some())
+            .finish()
+    }
+}
+
+/// Where an asset comes from; also decides its position in the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AssetScope {
+    /// Site-wide files such as `global.css`.
+    Global,
+    /// Files belonging to one page such as `home_page.js`.
+    Page,
+    /// Files shipped by a component such as `button_component.css`.
+    Component,
+}
+
+/// An ordered, de-duplicated list of file names.
+#[derive(Debug, Clone, Default)]
+struct AssetList {
+    entries: Vec<(AssetScope, String)>,
+}
+
+impl AssetList {
+    fn add(&mut self, scope: AssetScope, file_name: &str) {
+        match self.entries.iter_mut().find(|(_, name)| name == file_name) {
+            // Already registered: keep a single entry, in the most general scope.
+            Some(entry) => entry.0 = entry.0.min(scope),
+            None => self.entries.push((scope, file_name.to_string())),
+        }
+    }
+
+    /// File names sorted by scope; registration order is kept within a scope.
+    fn ordered(&self) -> Vec<&str> {
+        let mut entries: Vec<&(AssetScope, String)> = self.entries.iter().collect();
+        entries.sort_by_key(|(scope, _)| *scope); // stable sort
+        entries.into_iter().map(|(_, name)| name.as_str()).collect()
+    }
+}
+
+/// The CSS/JS files used by one page render.
+#[derive(Debug, Clone, Default)]
+pub struct AssetCollector {
+    context: AssetContext,
+    styles: AssetList,
+    head_scripts: AssetList,
+    bottom_scripts: AssetList,
+}
+
+impl AssetCollector {
+    /// An empty collector whose tags are printed with `context`.
+    pub fn new(context: AssetContext) -> Self {
+        Self {
+            context,
+            ..Self::default()
+        }
+    }
+
+    /// Registers a stylesheet, emitted as a `<link>` in the `<head>`.
+    pub fn add_style(&mut self, scope: AssetScope, file_name: &str) {
+        self.styles.add(scope, file_name);
+    }
+
+    /// Registers a script to be loaded in the `<head>`.
+    pub fn add_head_script(&mut self, scope: AssetScope, file_name: &str) {
+        self.head_scripts.add(scope, file_name);
+    }
+
+    /// Registers a script to be loaded at the bottom of `<body>`.
+    pub fn add_bottom_script(&mut self, scope: AssetScope, file_name: &str) {
+        self.bottom_scripts.add(scope, file_name);
+    }
+
+    pub fn style_file_names(&self) -> Vec<&str> {
+        self.styles.ordered()
+    }
+
+    pub fn head_script_file_names(&self) -> Vec<&str> {
+        self.head_scripts.ordered()
+    }
+
+    pub fn bottom_script_file_names(&self) -> Vec<&str> {
+        self.bottom_scripts.ordered()
+    }
+
+    /// Generates one `<link>` tag per registered stylesheet (or one for their bundle).
+    pub fn print_styles_head_tags(&self) -> String {
+        self.context.group_urls(AssetKind::Style, &self.style_file_names())
+            .iter()
+            .map(|url| format!(r#"<link rel="stylesheet" href="{}">"#, url))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Inlines the registered stylesheets into a `<style>` block, in order, as
+    /// long as they fit in `budget` bytes, and loads the rest asynchronously
+    /// (with a `<noscript>` fallback). Inlining stops at the first file that
+    /// doesn't fit so the cascade order stays the same as with `<link>` tags.
+    pub fn print_styles_head_tags_inlined(&self, budget: usize) -> String {
+        let file_names = self.style_file_names();
+        let mut inlined = String::new();
+        let mut inlined_count = 0;
+        for file_name in &file_names {
+            let Some(css) = self.context.read(file_name) else {
+                break;
+            };
+            let css = strip_source_map_comments(&css);
+            // HTML closes the element on `</style` in any case.
+            if css.to_ascii_lowercase().contains("</style") || inlined.len() + css.len() > budget {
+                break;
+            }
+            inlined.push_str(&css);
+            inlined_count += 1;
+        }
+
+        let mut tags = Vec::new();
+        if !inlined.is_empty() {
+            tags.push(format!("<style>\n{}</style>", inlined));
+        }
+        for url in self.context.group_urls(AssetKind::Style, &file_names[inlined_count..]) {
+            tags.push(format!(
+                r#"<link rel="preload" href="{url}" as="style" onload="this.onload=null;this.rel='stylesheet'">
+<noscript><link rel="stylesheet" href="{url}"></noscript>"#,
+                url = url
+            ));
+        }
+        tags.join("\n")
+    }
+
+    /// Generates one `<script>` tag per registered head script (or one for their bundle).
+    pub fn print_javascript_head_tags(&self) -> String {
+        self.context.group_urls(AssetKind::Script, &self.head_script_file_names())
+            .iter()
+            .map(|url| format!(r#"<script src="{}"></script>"#, url))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Generates one `<script>` tag per registered body-bottom script (or one for their bundle).
+    pub fn print_javascript_bottom_tags(&self) -> String {
+        self.context.group_urls(AssetKind::Script, &self.bottom_script_file_names())
+            .iter()
+            .map(|url| format!(r#"<script src="{}"></script>"#, url))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_registered_by_several_instances_are_printed_once() {
+        let mut assets = AssetCollector::default();
+        assets.add_style(AssetScope::Component, "button_component.css");
+        assets.add_style(AssetScope::Component, "button_component.css");
+        assets.add_bottom_script(AssetScope::Component, "button_component.js");
+        assets.add_bottom_script(AssetScope::Component, "button_component.js");
+
+        assert_eq!(assets.print_styles_head_tags(), r#"<link rel="stylesheet" href="/button_component.css">"#);
+        assert_eq!(assets.print_javascript_bottom_tags(), r#"<script src="/button_component.js"></script>"#);
+    }
+
+    #[test]
+    fn files_move_to_the_most_general_scope_they_are_registered_in() {
+        let mut assets = AssetCollector::default();
+        assets.add_style(AssetScope::Component, "shared.css");
+        assets.add_style(AssetScope::Page, "home_page.css");
+        assets.add_style(AssetScope::Global, "global.css");
+        assets.add_style(AssetScope::Global, "shared.css");
+
+        assert_eq!(assets.style_file_names(), ["shared.css", "global.css", "home_page.css"]);
+    }
+
+    struct Files(HashMap<&'static str, &'static str>);
+
+    impl AssetReader for Files {
+        fn read(&self, file_name: &str) -> Option<Arc<str>> {
+            self.0.get(file_name).map(|css| Arc::from(*css))
+        }
+    }
+
+    #[test]
+    fn stylesheets_that_would_close_the_style_element_are_linked() {
+        let files = Files(HashMap::from([
+            ("global.css", "body { margin: 0; }\n"),
+            ("quote.css", "q::after { content: \"</STYLE>\"; }\n"),
+        ]));
+        let mut assets = AssetCollector::new(AssetContext::default().with_reader(Arc::new(files)));
+        assets.add_style(AssetScope::Global, "global.css");
+        assets.add_style(AssetScope::Page, "quote.css");
+
+        let tags = assets.print_styles_head_tags_inlined(1024);
+        assert!(tags.starts_with("<style>\nbody { margin: 0; }\n</style>\n"), "{tags}");
+        assert!(tags.contains(r#"<link rel="preload" href="/quote.css""#), "{tags}");
+        assert!(!tags.contains("content:"), "{tags}");
+    }
+
+    #[test]
+    fn groups_come_out_global_page_component_in_registration_order() {
+        let mut assets = AssetCollector::default();
+        assets.add_style(AssetScope::Component, "button_component.css");
+        assets.add_style(AssetScope::Page, "home_page.css");
+        assets.add_style(AssetScope::Component, "task_card_001.css");
+        assets.add_style(AssetScope::Global, "global.css");
+        assets.add_style(AssetScope::Page, "about_page.css");
+        assets.add_style(AssetScope::Global, "fonts.css");
+
+        assert_eq!(
+            assets.style_file_names(),
+            ["global.css", "fonts.css", "home_page.css", "about_page.css", "button_component.css", "task_card_001.css"]
+        );
+    }
+}
@@ -0,0 +1,238 @@
+// pages_components/src/component.rs
+
+use std::collections::BTreeMap;
+
+use crate::assets::{AssetCollector, AssetContext, AssetScope};
+
+/// Attribute naming the component on its root element. The build scopes each
+/// component stylesheet to it (`build/scope_css.rs`), so `.my-button` only
+/// styles buttons rendered by `button_component`.
+pub const SCOPE_ATTRIBUTE: &str = "data-component";
+
+/// Adds `data-component="<scope>"` to the first element of `markup`, past any
+/// comments, doctype or other declarations in front of it.
+/// Components call this from `print_component_markup` with their folder name.
+pub fn scope_root_element(markup: &str, scope: &str) -> String {
+    let Some(tag_start) = first_start_tag(markup) else {
+        return markup.to_string();
+    };
+    let name_end = markup[tag_start + 1..]
+        .find(|character: char| character.is_ascii_whitespace() || character == '>' || character == '/')
+        .map_or(markup.len(), |offset| tag_start + 1 + offset);
+    format!(
+        r#"{} {}="{}"{}"#,
+        &markup[..name_end],
+        SCOPE_ATTRIBUTE,
+        scope,
+        &markup[name_end..]
+    )
+}
+
+/// Offset of the `<` of the first start tag (`<div`) in `markup`.
+fn first_start_tag(markup: &str) -> Option<usize> {
+    let mut position = 0;
+    while let Some(offset) = markup[position..].find('<') {
+        let start = position + offset;
+        let rest = &markup[start..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            position = start + 4 + comment.find("-->")? + 3;
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            position = start + rest.find('>')? + 1;
+        } else if rest[1..].starts_with(|character: char| character.is_ascii_alphabetic()) {
+            return Some(start);
+        } else {
+            position = start + 1;
+        }
+    }
+    None
+}
+
+/// The logical name of `file_name`, shipped in the folder of `component`: the
+/// `components/<component>/<file_name>` path build.rs stages it under with the
+/// `namespaced-assets` feature, `file_name` itself otherwise.
+pub fn component_asset_name(component: &str, file_name: &str) -> String {
+    if cfg!(feature = "namespaced-assets") {
+        format!("components/{}/{}", component, file_name)
+    } else {
+        file_name.to_string()
+    }
+}
+
+/// Behaviour shared by every UI component in this crate.
+///
+/// A component only has to expose its markup, its asset file names and its ids;
+/// the `<link>`/`<script>` tag printers are provided here so every component
+/// renders its assets the same way. Pages work with `&dyn Component`, which lets
+/// them collect markup and assets without knowing the concrete component types.
+pub trait Component {
+    /// Returns the HTML markup for the component.
+    fn print_component_markup(&self) -> String;
+
+    /// CSS file names for this component (e.g. "button_component.css"), as
+    /// logical asset names (see `component_asset_name`).
+    fn css_file_names(&self) -> &[String];
+
+    /// JavaScript file names to be placed at the bottom of `<body>`.
+    fn js_file_names(&self) -> &[String];
+
+    /// JavaScript file names to be placed in the `<head>`, if any.
+    fn head_js_file_names(&self) -> Option<&[String]>;
+
+    /// Returns a string representation of the component's unique ID.
+    /// This could be used for JavaScript to target specific component instances.
+    fn print_component_ids(&self) -> String;
+
+    /// Ids of the elements inside the component that scripts may target.
+    fn sub_component_ids(&self) -> &[String] {
+        &[]
+    }
+
+    /// Components rendered inside this one (see `Slots`).
+    fn children(&self) -> Vec<&dyn Component> {
+        Vec::new()
+    }
+
+    /// Registers the component's CSS and JS files, then its children's, with
+    /// the page being rendered.
+    fn register_assets(&self, assets: &mut AssetCollector) {
+        for file_name in self.css_file_names() {
+            assets.add_style(AssetScope::Component, file_name);
+        }
+        for file_name in self.head_js_file_names().unwrap_or_default() {
+            assets.add_head_script(AssetScope::Component, file_name);
+        }
+        for file_name in self.js_file_names() {
+            assets.add_bottom_script(AssetScope::Component, file_name);
+        }
+        for child in self.children() {
+            child.register_assets(assets);
+        }
+    }
+
+    /// Generates HTML `<link>` tags for the component's CSS files.
+    /// These are typically placed in the `<head>` section of the HTML.
+    fn print_styles_head_tags(&self, assets: &AssetContext) -> String {
+        self.css_file_names()
+            .iter()
+            .map(|file_name| format!(r#"<link rel="stylesheet" href="{}">"#, assets.url(file_name)))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Generates HTML `<script>` tags for JavaScript files to be placed in the `<head>`.
+    fn print_javascript_head_tags(&self, assets: &AssetContext) -> String {
+        self.head_js_file_names()
+            .map_or(String::new(), |files| { // If Some, map; otherwise, empty string
+                files.iter()
+                    .map(|file_name| format!(r#"<script src="{}"></script>"#, assets.url(file_name)))
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            })
+    }
+
+    /// Generates HTML `<script>` tags for JavaScript files to be placed at the bottom of `<body>`.
+    fn print_javascript_bottom_tags(&self, assets: &AssetContext) -> String {
+        self.js_file_names()
+            .iter()
+            .map(|file_name| format!(r#"<script src="{}"></script>"#, assets.url(file_name)))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+/// Child components a parent renders in place, by slot name.
+///
+/// The parent's template has a raw placeholder per slot (`{{{footer}}}`) filled
+/// with `render(slot)`, and the parent returns `children()` from its
+/// `Component::children` so the children's assets reach the page.
+#[derive(Default)]
+pub struct Slots {
+    slots: BTreeMap<String, Vec<Box<dyn Component>>>,
+}
+
+impl Slots {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `child` to `slot`; a slot can hold several children.
+    pub fn insert(&mut self, slot: &str, child: Box<dyn Component>) {
+        self.slots.entry(slot.to_string()).or_default().push(child);
+    }
+
+    /// The markup of the children in `slot`, in insertion order.
+    pub fn render(&self, slot: &str) -> String {
+        self.slots
+            .get(slot)
+            .map(|children| {
+                children
+                    .iter()
+                    .map(|child| child.print_component_markup())
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            })
+            .unwrap_or_default()
+    }
+
+    /// Every child, slot by slot.
+    pub fn children(&self) -> Vec<&dyn Component> {
+        self.slots.values().flatten().map(|child| child.as_ref()).collect()
+    }
+}
+
+/// Components a template includes by name (`{{> TaskCard001 task}}`).
+///
+/// `#[derive(Component)]` builds them together with their parent, in the order
+/// the template renders them, and hands them back to the template one by one.
+#[derive(Default)]
+pub struct Includes {
+    components: Vec<Box<dyn Component>>,
+}
+
+impl Includes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, component: Box<dyn Component>) {
+        self.components.push(component);
+    }
+
+    /// Every included component, in template order.
+    pub fn children(&self) -> Vec<&dyn Component> {
+        self.components.iter().map(|component| component.as_ref()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_root_element_is_tagged_with_the_scope() {
+        assert_eq!(
+            scope_root_element("<div class=\"card\"><p>x</p></div>", "card"),
+            r#"<div data-component="card" class="card"><p>x</p></div>"#
+        );
+        assert_eq!(scope_root_element("<br/>", "line"), r#"<br data-component="line"/>"#);
+    }
+
+    #[test]
+    fn comments_and_declarations_before_the_root_are_skipped() {
+        assert_eq!(
+            scope_root_element("<!-- <b> card --><div>x</div>", "card"),
+            r#"<!-- <b> card --><div data-component="card">x</div>"#
+        );
+        assert_eq!(
+            scope_root_element("<!DOCTYPE html>\n<?xml-stylesheet?><section></section>", "page"),
+            "<!DOCTYPE html>\n<?xml-stylesheet?><section data-component=\"page\"></section>"
+        );
+    }
+
+    #[test]
+    fn markup_without_an_element_is_left_alone() {
+        assert_eq!(scope_root_element("just text", "card"), "just text");
+        assert_eq!(scope_root_element("<!-- only a comment -->", "card"), "<!-- only a comment -->");
+        assert_eq!(scope_root_element("a < b", "card"), "a < b");
+    }
+}
@@ -1,8 +1,13 @@
 document.addEventListener('DOMContentLoaded', () => {
-    const button = document.querySelector('.my-button'); // Or use the dynamic ID
-    if (button) {
-        button.addEventListener('click', () => {
-            alert('Button clicked from component JS!');
-        });
+    // Every button rendered on the page, by the ids the server gave them.
+    const instancesScript = document.getElementById('component-instances');
+    const instances = instancesScript ? JSON.parse(instancesScript.textContent) : {};
+    for (const { id } of instances.button_component || []) {
+        const button = document.getElementById(id);
+        if (button) {
+            button.addEventListener('click', () => {
+                alert('Button clicked from component JS!');
+            });
+        }
     }
-});
\ No newline at end of file
+});
@@ -1,3 +1,4 @@
 
 pub mod button_component;
-pub mod task_card_001;
\ No newline at end of file
+pub mod task_card_001;
+pub mod task_list_001;
\ No newline at end of file
@@ -0,0 +1,83 @@
+// pages_components/src/components/task_list_001.rs
+
+use serde::{Deserialize, Serialize};
+
+use crate::component::Includes;
+use crate::components::task_card_001::{TaskCard001, TaskCard001Props};
+use crate::ids::ComponentIds;
+use crate::{Component, Props};
+
+/// Data rendered by `task_list_001.html`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Props)]
+pub struct TaskList001Props {
+    pub title: String,
+    /// One `task_card_001` is rendered per task, in order.
+    #[serde(default)]
+    pub tasks: Vec<TaskCard001Props>,
+}
+
+/// A titled list of task cards, or a placeholder text when there are no tasks.
+///
+/// The cards are included by the template (`{{> TaskCard001 task}}`) and take
+/// the `task_card_001-N` ids after the list's own `task_list_001-N`.
+#[derive(Component)]
+#[component(
+    html = "task_list_001/task_list_001.html",
+    css = ["task_list_001.css"],
+)]
+pub struct TaskList001 {
+    props: TaskList001Props,
+    ids: ComponentIds,
+    includes: Includes,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IdAllocator;
+
+    fn task(title: &str) -> TaskCard001Props {
+        TaskCard001Props {
+            title: title.to_string(),
+            ..TaskCard001Props::default()
+        }
+    }
+
+    #[test]
+    fn included_cards_render_in_the_order_they_were_built() {
+        let mut ids = IdAllocator::new();
+        let list = TaskList001::new(
+            TaskList001Props {
+                title: String::from("Tasks"),
+                tasks: vec![task("First"), task("Second"), task("Third")],
+            },
+            &mut ids,
+        );
+        let markup = list.print_component_markup();
+
+        // `new` built the cards in template order, so each card's ids sit next
+        // to the task it was built for.
+        let positions: Vec<usize> = [
+            r#"id="task_card_001-1""#,
+            "First",
+            r#"id="task_card_001-2""#,
+            "Second",
+            r#"id="task_card_001-3""#,
+            "Third",
+        ]
+        .iter()
+        .map(|needle| markup.find(needle).unwrap_or_else(|| panic!("{} is missing from {}", needle, markup)))
+        .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]), "{}", markup);
+        assert_eq!(list.children().len(), 3);
+    }
+
+    #[test]
+    fn an_empty_list_includes_no_cards() {
+        let mut ids = IdAllocator::new();
+        let list = TaskList001::new(TaskList001Props::default(), &mut ids);
+        assert!(list.print_component_markup().contains("Nothing to do."));
+        assert!(list.children().is_empty());
+        assert!(ids.instances("task_card_001").is_empty());
+    }
+}
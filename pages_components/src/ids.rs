@@ -0,0 +1,77 @@
+// pages_components/src/ids.rs
+
+// Hands out the DOM ids of the component instances rendered for one request.
+//
+// A page creates one `IdAllocator` per render and passes it to every component
+// it builds. Ids are the component name plus a counter (`task_card_001-1`,
+// `task_card_001-2`), and sub-element ids append their name to it
+// (`task_card_001-1-header`), so two instances never share an id and the same
+// component tree always gets the same ids.
+//
+// The allocator also remembers every instance; the layout prints them as JSON
+// so component scripts can find their own elements:
+//
+//     <script type="application/json" id="component-instances">
+//     {"task_card_001":[{"id":"task_card_001-1","sub_ids":["task_card_001-1-header","task_card_001-1-body"]}]}
+//     </script>
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// `id` of the `<script>` element holding the instances JSON.
+pub const INSTANCES_SCRIPT_ID: &str = "component-instances";
+
+/// The ids given to one component instance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComponentIds {
+    /// Id of the component's root element.
+    pub id: String,
+    /// Ids of the elements inside the component, in the order they were asked for.
+    pub sub_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IdAllocator {
+    // Component name -> ids of its instances, in allocation order.
+    instances: BTreeMap<String, Vec<ComponentIds>>,
+}
+
+impl IdAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ids for the next instance of `component`, with one sub id per entry of `sub_names`.
+    pub fn allocate(&mut self, component: &str, sub_names: &[&str]) -> ComponentIds {
+        let instances = self.instances.entry(component.to_string()).or_default();
+        let id = format!("{}-{}", component, instances.len() + 1);
+        let sub_ids = sub_names
+            .iter()
+            .map(|sub_name| format!("{}-{}", id, sub_name))
+            .collect();
+        let ids = ComponentIds { id, sub_ids };
+        instances.push(ids.clone());
+        ids
+    }
+
+    /// Ids of every instance of `component` allocated so far.
+    pub fn instances(&self, component: &str) -> &[ComponentIds] {
+        self.instances.get(component).map_or(&[], Vec::as_slice)
+    }
+
+    /// The JSON `<script>` listing every instance, or nothing when no ids were handed out.
+    pub fn print_instances_script(&self) -> String {
+        if self.instances.is_empty() {
+            return String::new();
+        }
+        let json = serde_json::to_string(&self.instances)
+            .expect("component ids should serialize to JSON")
+            // Keep `</script>` in a value from closing the element.
+            .replace("</", "<\\/");
+        format!(
+            r#"<script type="application/json" id="{}">{}</script>"#,
+            INSTANCES_SCRIPT_ID, json
+        )
+    }
+}
@@ -1,6 +1,20 @@
+// Lets the code generated by `#[derive(Component)]` name this crate as
+// `::pages_components` from inside it too.
+extern crate self as pages_components;
+
+pub mod assets;
+pub mod component;
 pub mod components;
+pub mod ids;
+pub mod template;
 
 // Re-export specific components for easier access from consuming crates.
 // This allows `use pages_components::ButtonComponent;` instead of `use pages_components::components::button_component::ButtonComponent;`
-pub use components::button_component::ButtonComponent;
-pub use components::task_card_001::TaskCard001;
\ No newline at end of file
+pub use assets::{AssetBundler, AssetCollector, AssetContext, AssetKind, AssetManifest, AssetReader, AssetScope};
+pub use component::{Component, Includes, Slots};
+pub use pages_components_macros::{Component, Props};
+pub use ids::{ComponentIds, IdAllocator};
+pub use template::Props;
+pub use components::button_component::{ButtonComponent, ButtonComponentProps};
+pub use components::task_card_001::{ChecklistItem, TaskCard001, TaskCard001Props};
+pub use components::task_list_001::{TaskList001, TaskList001Props};
\ No newline at end of file
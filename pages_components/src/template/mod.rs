@@ -0,0 +1,563 @@
+// pages_components/src/template/mod.rs
+
+// A very small template engine for the component `.html` files.
+//
+// Syntax:
+// - `{{ name }}`   inserts the value of `name`, HTML-escaped.
+// - `{{{ name }}}` inserts the value of `name` untouched (raw opt-out, use only
+//   for markup you produced yourself, e.g. the output of another component).
+// - `{{ item.text }}` reads the field `text` of the struct `item`.
+// - `{{#if done}} ... {{else}} ... {{/if}}` renders the first branch when
+//   `done` is true, a non-empty string or list, a non-zero number or a struct.
+//   `{{else}}` is optional.
+// - `{{#for item in checklist}} ... {{else}} ... {{/for}}` renders its body
+//   once per element of the list `checklist`, with the element available as
+//   `item`, or the part after the optional `{{else}}` when the list is empty.
+// - `{{> TaskCard001 task}}` renders another component, built with `task` as
+//   its props (or its default props without one). Only components deriving
+//   `Component` can include others; see `pages_components_macros`.
+//
+// Every placeholder must have a value in the `Context`; a missing value is an
+// error rather than an empty string so typos in templates don't go unnoticed.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub use pages_template_syntax::{Node, Template, TemplateError};
+
+/// A typed value that can be substituted into a template.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Renders as an empty string (e.g. a `None` prop).
+    Null,
+    Text(String),
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    /// A collection, for `{{#for}}`.
+    List(Vec<Value>),
+    /// A nested struct, read with dotted names (`{{ item.text }}`).
+    Map(BTreeMap<String, Value>),
+}
+
+impl Value {
+    /// Whether `{{#if}}` renders its first branch for this value.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Text(text) => !text.is_empty(),
+            Value::Bool(value) => *value,
+            Value::Integer(value) => *value != 0,
+            Value::Float(value) => *value != 0.0,
+            Value::List(items) => !items.is_empty(),
+            Value::Map(_) => true,
+        }
+    }
+
+    /// Reads the value back into a props type, e.g. the props of an included component.
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_value(self.to_json())
+    }
+
+    fn from_json(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(value) => Value::Bool(value),
+            serde_json::Value::Number(number) => match number.as_i64() {
+                Some(value) => Value::Integer(value),
+                None => Value::Float(number.as_f64().unwrap_or_default()),
+            },
+            serde_json::Value::String(value) => Value::Text(value),
+            serde_json::Value::Array(items) => Value::List(items.into_iter().map(Value::from_json).collect()),
+            serde_json::Value::Object(fields) => Value::Map(
+                fields
+                    .into_iter()
+                    .map(|(name, field)| (name, Value::from_json(field)))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Null => serde_json::Value::Null,
+            Value::Text(text) => serde_json::Value::String(text.clone()),
+            Value::Bool(value) => serde_json::Value::Bool(*value),
+            Value::Integer(value) => serde_json::Value::from(*value),
+            Value::Float(value) => serde_json::Value::from(*value),
+            Value::List(items) => serde_json::Value::Array(items.iter().map(Value::to_json).collect()),
+            Value::Map(fields) => serde_json::Value::Object(
+                fields
+                    .iter()
+                    .map(|(name, field)| (name.clone(), field.to_json()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Text(text) => f.write_str(text),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Integer(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::List(_) | Value::Map(_) => write!(f, "{}", self.to_json()),
+        }
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<&String> for Value {
+    fn from(value: &String) -> Self {
+        Value::Text(value.clone())
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(value: Vec<T>) -> Self {
+        Value::List(value.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+/// The named values a template is rendered against.
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    values: BTreeMap<String, Value>,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds (or replaces) a value, builder style:
+    /// `Context::new().with("label", "Save").with("disabled", false)`.
+    pub fn with(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    /// Adds (or replaces) a value.
+    pub fn insert(&mut self, name: &str, value: impl Into<Value>) {
+        self.values.insert(name.to_string(), value.into());
+    }
+
+    /// Builds a context from a props struct: every field becomes a value of the same name.
+    ///
+    /// Strings, numbers, booleans and `Option`s of those render as text;
+    /// collections become lists for `{{#for}}` and nested structs are read with
+    /// dotted names.
+    pub fn from_serialize<T: Serialize>(props: &T) -> Result<Self, TemplateError> {
+        let fields = match serde_json::to_value(props) {
+            Ok(serde_json::Value::Object(fields)) => fields,
+            _ => return Err(TemplateError::UnsupportedProps),
+        };
+
+        let mut context = Self::new();
+        for (name, field) in fields {
+            context.values.insert(name, Value::from_json(field));
+        }
+        Ok(context)
+    }
+
+    /// Like `from_serialize`, with a value for every name in `T::FIELDS`: the
+    /// fields serde leaves out (`skip_serializing_if`) are `Null` and render
+    /// as an empty string. `#[derive(Component)]` renders with this context.
+    pub fn from_props<T: Serialize + Props>(props: &T) -> Result<Self, TemplateError> {
+        let mut context = Self::from_serialize(props)?;
+        for name in T::FIELDS {
+            context.values.entry(name.to_string()).or_insert(Value::Null);
+        }
+        Ok(context)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+}
+
+/// Props whose field names are known at compile time, so `#[derive(Component)]`
+/// can check every placeholder of a template against them.
+///
+/// Implement it with `#[derive(Props)]`; the names follow serde's `rename` and
+/// `skip`, like `Context::from_serialize`.
+pub trait Props {
+    /// The names of the fields a template can use.
+    const FIELDS: &'static [&'static str];
+}
+
+/// Whether `name` is one of `fields`; used by the template checks that
+/// `#[derive(Component)]` evaluates at compile time.
+#[doc(hidden)]
+pub const fn has_field(fields: &[&str], name: &str) -> bool {
+    let mut index = 0;
+    while index < fields.len() {
+        if str_equals(fields[index], name) {
+            return true;
+        }
+        index += 1;
+    }
+    false
+}
+
+/// Type-checks the props of a `{{> Component props}}` tag against the
+/// component's `new`; used by the checks of `#[derive(Component)]`.
+#[doc(hidden)]
+pub fn check_include<P, C>(_new: fn(P, &mut crate::ids::IdAllocator) -> C, _props: &P) {}
+
+/// Like `check_include`, for a `{{> Component}}` tag built with default props.
+#[doc(hidden)]
+pub fn check_default_include<P: Default, C>(_new: fn(P, &mut crate::ids::IdAllocator) -> C) {}
+
+const fn str_equals(left: &str, right: &str) -> bool {
+    let (left, right) = (left.as_bytes(), right.as_bytes());
+    if left.len() != right.len() {
+        return false;
+    }
+    let mut index = 0;
+    while index < left.len() {
+        if left[index] != right[index] {
+            return false;
+        }
+        index += 1;
+    }
+    true
+}
+
+/// Renders `{{> Component props}}`: gets the component name, its props (`None`
+/// when the tag has none) and the template line, returns the component's markup.
+pub type IncludeRenderer<'r> = dyn FnMut(&str, Option<&Value>, usize) -> Result<String, TemplateError> + 'r;
+
+/// Renders `template` against `context`. Fails on `{{> Component}}`.
+pub fn render_template(template: &Template, context: &Context) -> Result<String, TemplateError> {
+    render_with_includes(template, context, &mut |component, _, line| {
+        Err(TemplateError::UnexpectedInclude {
+            component: component.to_string(),
+            line,
+        })
+    })
+}
+
+/// Renders `template`, asking `include` for the markup of every
+/// `{{> Component}}` in the order they are rendered.
+pub fn render_with_includes(
+    template: &Template,
+    context: &Context,
+    include: &mut IncludeRenderer,
+) -> Result<String, TemplateError> {
+    let mut scope = Scope {
+        context,
+        locals: Vec::new(),
+    };
+    let mut output = String::new();
+    render_nodes(template.nodes(), &mut scope, include, &mut output)?;
+    Ok(output)
+}
+
+// The context plus the variables of the enclosing `{{#for}}` loops.
+struct Scope<'c> {
+    context: &'c Context,
+    locals: Vec<(&'c str, Value)>,
+}
+
+impl Scope<'_> {
+    fn lookup(&self, path: &str, line: usize) -> Result<&Value, TemplateError> {
+        let mut segments = path.split('.');
+        let root = segments.next().unwrap_or_default();
+        let mut value = self
+            .locals
+            .iter()
+            .rev()
+            .find(|(name, _)| *name == root)
+            .map(|(_, value)| value)
+            .or_else(|| self.context.get(root));
+        for segment in segments {
+            value = match value {
+                Some(Value::Map(fields)) => fields.get(segment),
+                _ => None,
+            };
+        }
+        value.ok_or_else(|| TemplateError::MissingValue {
+            name: path.to_string(),
+            line,
+        })
+    }
+}
+
+fn render_nodes<'c>(
+    nodes: &[Node<'c>],
+    scope: &mut Scope<'c>,
+    include: &mut IncludeRenderer,
+    output: &mut String,
+) -> Result<(), TemplateError> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Placeholder { name, raw, line } => {
+                let value = scope.lookup(name, *line)?;
+                if matches!(value, Value::List(_) | Value::Map(_)) {
+                    return Err(TemplateError::NotText {
+                        name: name.to_string(),
+                        line: *line,
+                    });
+                }
+                if *raw {
+                    output.push_str(&value.to_string());
+                } else {
+                    output.push_str(&escape_html(&value.to_string()));
+                }
+            }
+            Node::If {
+                condition,
+                then,
+                otherwise,
+                line,
+            } => {
+                let branch = if scope.lookup(condition, *line)?.is_truthy() {
+                    then
+                } else {
+                    otherwise
+                };
+                render_nodes(branch, scope, include, output)?;
+            }
+            Node::For {
+                item,
+                list,
+                body,
+                empty,
+                line,
+            } => {
+                let items = match scope.lookup(list, *line)? {
+                    Value::List(items) => items.clone(),
+                    // A `None` list renders nothing.
+                    Value::Null => Vec::new(),
+                    _ => {
+                        return Err(TemplateError::NotIterable {
+                            name: list.to_string(),
+                            line: *line,
+                        });
+                    }
+                };
+                if items.is_empty() {
+                    render_nodes(empty, scope, include, output)?;
+                }
+                for value in items {
+                    scope.locals.push((item, value));
+                    let result = render_nodes(body, scope, include, output);
+                    scope.locals.pop();
+                    result?;
+                }
+            }
+            Node::Include { component, props, line } => {
+                let props = props.map(|props| scope.lookup(props, *line)).transpose()?;
+                output.push_str(&include(component, props, *line)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parses and renders `source` in one go.
+pub fn render(source: &str, context: &Context) -> Result<String, TemplateError> {
+    render_template(&Template::parse(source)?, context)
+}
+
+/// Escapes the characters that are significant in HTML text and attribute values.
+pub fn escape_html(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for character in input.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_html_escapes_markup_and_quotes() {
+        assert_eq!(
+            escape_html(r#"<a href="x" title='y'>Tom & Jerry</a>"#),
+            "&lt;a href=&quot;x&quot; title=&#x27;y&#x27;&gt;Tom &amp; Jerry&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn escape_html_leaves_plain_text_alone() {
+        assert_eq!(escape_html("Plain text, ünïcode and 100%"), "Plain text, ünïcode and 100%");
+        assert_eq!(escape_html(""), "");
+    }
+
+    #[test]
+    fn escaped_placeholders_escape_their_value() {
+        let context = Context::new().with("label", "<b>Save</b>");
+        assert_eq!(
+            render("<button>{{label}}</button>", &context).unwrap(),
+            "<button>&lt;b&gt;Save&lt;/b&gt;</button>"
+        );
+    }
+
+    #[test]
+    fn raw_placeholders_insert_their_value_as_is() {
+        let context = Context::new().with("label", "<b>Save</b>");
+        assert_eq!(
+            render("<button>{{{label}}}</button>", &context).unwrap(),
+            "<button><b>Save</b></button>"
+        );
+    }
+
+    #[test]
+    fn for_blocks_render_their_else_part_for_empty_lists() {
+        #[derive(Serialize)]
+        struct Props {
+            items: Vec<&'static str>,
+        }
+        let source = "<ul>{{#for item in items}}<li>{{item}}</li>{{else}}<li>None</li>{{/for}}</ul>";
+        let full = Context::from_serialize(&Props { items: vec!["a", "b"] }).unwrap();
+        assert_eq!(render(source, &full).unwrap(), "<ul><li>a</li><li>b</li></ul>");
+        let empty = Context::from_serialize(&Props { items: vec![] }).unwrap();
+        assert_eq!(render(source, &empty).unwrap(), "<ul><li>None</li></ul>");
+    }
+
+    #[test]
+    fn placeholders_may_have_spaces_inside_the_braces() {
+        let context = Context::new().with("label", "Save").with("icon", "<i></i>");
+        assert_eq!(render("{{ label }} {{{ icon }}}", &context).unwrap(), "Save <i></i>");
+    }
+
+    #[test]
+    fn values_render_as_text() {
+        let context = Context::new()
+            .with("count", 3_i64)
+            .with("ratio", 0.5)
+            .with("done", true)
+            .with("due", None::<String>);
+        assert_eq!(render("{{count}} {{ratio}} {{done}} [{{due}}]", &context).unwrap(), "3 0.5 true []");
+    }
+
+    #[test]
+    fn missing_values_are_errors_with_their_line() {
+        let error = render("<div>\n  <p>{{title}}</p>\n  <p>{{body}}</p>\n</div>", &Context::new().with("title", "Hi"))
+            .unwrap_err();
+        assert_eq!(
+            error,
+            TemplateError::MissingValue {
+                name: String::from("body"),
+                line: 3,
+            }
+        );
+        assert_eq!(error.line(), Some(3));
+        assert_eq!(error.to_string(), "line 3: no value for placeholder `body`");
+    }
+
+    #[test]
+    fn line_numbers_count_newlines_inside_placeholders() {
+        let error = render("{{\nfirst\n}}\n{{second}}", &Context::new().with("first", "1")).unwrap_err();
+        assert_eq!(error.line(), Some(4));
+    }
+
+    #[test]
+    fn unclosed_placeholders_are_errors_with_their_line() {
+        let error = render("<p>\n{{title</p>", &Context::new()).unwrap_err();
+        assert_eq!(error, TemplateError::UnclosedPlaceholder { line: 2 });
+    }
+
+    #[test]
+    fn invalid_placeholders_are_errors() {
+        for source in ["{{}}", "{{ two words }}", "{{{a-b}}}", "{{a..b}}"] {
+            assert!(
+                matches!(render(source, &Context::new()), Err(TemplateError::InvalidPlaceholder { line: 1, .. })),
+                "{:?} should be rejected",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn props_become_the_context() {
+        #[derive(Serialize)]
+        struct Props {
+            title: String,
+            done: bool,
+        }
+        let context = Context::from_serialize(&Props {
+            title: String::from("Write tests"),
+            done: false,
+        })
+        .unwrap();
+        assert_eq!(render("{{title}}: {{done}}", &context).unwrap(), "Write tests: false");
+        assert_eq!(Context::from_serialize(&"text").unwrap_err(), TemplateError::UnsupportedProps);
+    }
+
+    #[test]
+    fn fields_serde_leaves_out_render_empty() {
+        #[derive(Serialize, crate::Props)]
+        struct NoteProps {
+            title: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            note: Option<String>,
+        }
+        let props = NoteProps {
+            title: String::from("Write tests"),
+            note: None,
+        };
+        let context = Context::from_props(&props).unwrap();
+        assert_eq!(render("{{title}}: {{note}}", &context).unwrap(), "Write tests: ");
+        assert_eq!(
+            render("{{note}}", &Context::from_serialize(&props).unwrap()).unwrap_err(),
+            TemplateError::MissingValue {
+                name: String::from("note"),
+                line: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,12 @@
+// pages_components/tests/compile_fail.rs
+
+// Template mistakes `#[derive(Component)]` must turn into compile errors that
+// name the template line. Each case under `compile_fail/` derives a component
+// from the template in the folder of the same name, and its `.stderr` holds
+// the expected error. Run with `TRYBUILD=overwrite` to update them.
+
+#[test]
+fn template_mistakes_are_compile_errors() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/compile_fail/*.rs");
+}
@@ -0,0 +1,17 @@
+use pages_components::{Component, ComponentIds, Props};
+use serde::Serialize;
+
+#[derive(Default, Serialize, Props)]
+pub struct CardProps {
+    title: String,
+    tags: Vec<String>,
+}
+
+#[derive(Component)]
+#[component(html = "id_in_loop/id_in_loop.html", sub_ids = ["tag"])]
+pub struct Card {
+    props: CardProps,
+    ids: ComponentIds,
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use pages_components::{Component, ComponentIds, Props};
+use serde::Serialize;
+
+#[derive(Default, Serialize, Props)]
+pub struct CardProps {
+    title: String,
+    tags: Vec<String>,
+}
+
+#[derive(Component)]
+#[component(html = "unclosed_tag/unclosed_tag.html")]
+pub struct Card {
+    props: CardProps,
+    ids: ComponentIds,
+}
+
+fn main() {}
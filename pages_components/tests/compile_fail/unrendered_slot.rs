@@ -0,0 +1,18 @@
+use pages_components::{Component, ComponentIds, Props, Slots};
+use serde::Serialize;
+
+#[derive(Default, Serialize, Props)]
+pub struct CardProps {
+    title: String,
+    tags: Vec<String>,
+}
+
+#[derive(Component)]
+#[component(html = "unrendered_slot/unrendered_slot.html", slots = ["footer"])]
+pub struct Card {
+    props: CardProps,
+    ids: ComponentIds,
+    slots: Slots,
+}
+
+fn main() {}
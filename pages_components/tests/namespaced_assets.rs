@@ -0,0 +1,60 @@
+// pages_components/tests/namespaced_assets.rs
+
+// Two components shipping a file with the same name, as the `namespaced-assets`
+// feature allows: each must register, and link, its own copy.
+
+#![cfg(feature = "namespaced-assets")]
+
+use std::sync::Arc;
+
+use pages_components::{AssetCollector, AssetContext, AssetManifest, Component, ComponentIds, IdAllocator, Props};
+use serde::Serialize;
+
+#[derive(Debug, Default, Serialize, Props)]
+pub struct LabelProps {
+    label: String,
+}
+
+#[derive(Component)]
+#[component(html = "fixtures/first_widget/first_widget.html", css = ["styles.css"])]
+pub struct FirstWidget {
+    props: LabelProps,
+    ids: ComponentIds,
+}
+
+#[derive(Component)]
+#[component(html = "fixtures/second_widget/second_widget.html", css = ["styles.css"])]
+pub struct SecondWidget {
+    props: LabelProps,
+    ids: ComponentIds,
+}
+
+#[test]
+fn same_named_files_of_two_components_get_a_link_each() {
+    let mut ids = IdAllocator::new();
+    let first = FirstWidget::new(LabelProps::default(), &mut ids);
+    let second = SecondWidget::new(LabelProps::default(), &mut ids);
+    assert_eq!(first.css_file_names(), ["components/first_widget/styles.css"]);
+    assert_eq!(second.css_file_names(), ["components/second_widget/styles.css"]);
+
+    // What build.rs writes for these two files under the feature.
+    let manifest = AssetManifest::from_json(
+        r#"{
+            "components/first_widget/styles.css": "components/first_widget/styles.1a2b3c4d.css",
+            "components/second_widget/styles.css": "components/second_widget/styles.5e6f7a8b.css"
+        }"#,
+    )
+    .unwrap();
+    let mut assets = AssetCollector::new(AssetContext::new(Arc::new(manifest)));
+    first.register_assets(&mut assets);
+    second.register_assets(&mut assets);
+
+    assert_eq!(
+        assets.print_styles_head_tags(),
+        [
+            r#"<link rel="stylesheet" href="/components/first_widget/styles.1a2b3c4d.css">"#,
+            r#"<link rel="stylesheet" href="/components/second_widget/styles.5e6f7a8b.css">"#,
+        ]
+        .join("\n")
+    );
+}
@@ -0,0 +1,88 @@
+// pages_components_macros/src/attributes.rs
+
+// Parses the `#[component(...)]` attribute of `#[derive(Component)]`.
+
+use syn::punctuated::Punctuated;
+use syn::{Attribute, LitStr, Token};
+
+/// What `#[component(...)]` declares. Every file name keeps the literal it was
+/// written as, so errors can point at it.
+pub struct ComponentAttributes {
+    /// Component name; must be the folder holding `html`, which is the default.
+    pub name: Option<LitStr>,
+    /// Template path, relative to the `.rs` file of the component.
+    pub html: LitStr,
+    /// Stylesheets, relative to the template's folder.
+    pub css: Vec<LitStr>,
+    /// Scripts for the bottom of `<body>`, relative to the template's folder.
+    pub js: Vec<LitStr>,
+    /// Scripts for the `<head>`, if the component has any.
+    pub head_js: Option<Vec<LitStr>>,
+    /// Names of the elements that get their own id (`header` -> `{{header_id}}`).
+    pub sub_ids: Vec<LitStr>,
+    /// Names of the slots that can hold child components (`footer` -> `{{{footer}}}`).
+    pub slots: Vec<LitStr>,
+}
+
+impl ComponentAttributes {
+    pub fn parse(attributes: &[Attribute], struct_span: proc_macro2::Span) -> syn::Result<Self> {
+        let mut name = None;
+        let mut html = None;
+        let mut css = Vec::new();
+        let mut js = Vec::new();
+        let mut head_js = None;
+        let mut sub_ids = Vec::new();
+        let mut slots = Vec::new();
+
+        for attribute in attributes.iter().filter(|attribute| attribute.path().is_ident("component")) {
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    name = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("html") {
+                    html = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("css") {
+                    css = parse_list(meta.value()?)?;
+                } else if meta.path.is_ident("js") {
+                    js = parse_list(meta.value()?)?;
+                } else if meta.path.is_ident("head_js") {
+                    head_js = Some(parse_list(meta.value()?)?);
+                } else if meta.path.is_ident("sub_ids") {
+                    sub_ids = parse_list(meta.value()?)?;
+                } else if meta.path.is_ident("slots") {
+                    slots = parse_list(meta.value()?)?;
+                } else {
+                    return Err(meta.error(
+                        "unknown component attribute, expected one of `name`, `html`, `css`, `js`, `head_js`, `sub_ids`, `slots`",
+                    ));
+                }
+                Ok(())
+            })?;
+        }
+
+        let html = html.ok_or_else(|| {
+            syn::Error::new(
+                struct_span,
+                r#"missing `#[component(html = "...")]` with the path of the component template"#,
+            )
+        })?;
+
+        Ok(Self {
+            name,
+            html,
+            css,
+            js,
+            head_js,
+            sub_ids,
+            slots,
+        })
+    }
+}
+
+/// `["a.css", "b.css"]`
+fn parse_list(input: syn::parse::ParseStream) -> syn::Result<Vec<LitStr>> {
+    let content;
+    syn::bracketed!(content in input);
+    Ok(Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?
+        .into_iter()
+        .collect())
+}
@@ -0,0 +1,319 @@
+// pages_components_macros/src/check.rs
+
+// The compile-time checks of `#[derive(Component)]`.
+//
+// Problems the macro can see itself (missing files, template syntax, unclosed
+// tags, stray ids, ids or slots repeated by a loop, blocks over ids or slots)
+// are reported right away, naming the template line. What needs the props type
+// is turned into code that only compiles when the template is right:
+//
+// - every name a placeholder, `{{#if}}`, `{{#for}}` or `{{> Component props}}`
+//   reads from the props is asserted to be in `Props::FIELDS`, in a constant
+//   whose panic message names the template line;
+// - the fields read from loop variables and the props given to included
+//   components are type-checked by mirroring the template's loops in a closure
+//   over the props (`for item in &props.checklist { let _ = &item.text; }`).
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::{Ident, LitStr, Member, Type};
+
+use crate::attributes::ComponentAttributes;
+use crate::html;
+use crate::template::{self, Node, Template};
+
+/// What the rest of the derive needs to know about the template.
+pub struct TemplateInfo {
+    /// Code that fails to compile when the template doesn't fit the props.
+    pub checks: TokenStream,
+    /// The components the template includes, and whether each is given props
+    /// (`{{> TaskCard001 task}}`) or not (`{{> TaskCard001}}`).
+    pub includes: BTreeSet<(String, bool)>,
+}
+
+pub fn check_template(
+    attributes: &ComponentAttributes,
+    source_dir: &Path,
+    props_type: &Type,
+) -> syn::Result<TemplateInfo> {
+    let html = &attributes.html;
+    let html_path = source_dir.join(html.value());
+    let source = std::fs::read_to_string(&html_path).map_err(|error| {
+        syn::Error::new(
+            html.span(),
+            format!("cannot read the template {}: {}", html_path.display(), error),
+        )
+    })?;
+
+    let assets_dir: PathBuf = html_path.parent().map_or(source_dir.to_path_buf(), Path::to_path_buf);
+    let head_js = attributes.head_js.iter().flatten();
+    for file_name in attributes.css.iter().chain(&attributes.js).chain(head_js) {
+        let path = assets_dir.join(file_name.value());
+        if !path.is_file() {
+            return Err(syn::Error::new(
+                file_name.span(),
+                format!("asset not found: {}", path.display()),
+            ));
+        }
+    }
+
+    // Errors point at the template line: `task_card_001/task_card_001.html:4: ...`.
+    let template_error = |line: usize, message: &str| {
+        syn::Error::new(html.span(), format!("{}:{}: {}", html.value(), line, message))
+    };
+
+    let parsed = Template::parse(&source).map_err(|error| {
+        let line = error.line().unwrap_or(1);
+        let message = error.to_string();
+        let message = message.strip_prefix(&format!("line {}: ", line)).unwrap_or(&message);
+        template_error(line, message)
+    })?;
+    let id_attributes = html::check(&source, parsed.nodes()).map_err(|error| template_error(error.line, &error.message))?;
+
+    let sub_id_placeholders: Vec<String> = attributes
+        .sub_ids
+        .iter()
+        .map(|sub_id| format!("{}_id", sub_id.value()))
+        .collect();
+    for id_attribute in &id_attributes {
+        let is_component_id = template::single_placeholder(&id_attribute.value)
+            .is_some_and(|name| name == "component_id" || sub_id_placeholders.iter().any(|sub_id| sub_id == name));
+        if is_component_id && id_attribute.in_loop {
+            return Err(template_error(
+                id_attribute.line,
+                &format!(
+                    "id `{}` is inside `{{{{#for}}}}`, so every item would repeat it; move it out of the loop",
+                    id_attribute.value
+                ),
+            ));
+        }
+        if !is_component_id {
+            return Err(template_error(
+                id_attribute.line,
+                &format!(
+                    "id `{}` is not one of the component's ids; use `{{{{component_id}}}}` or add a sub id to `sub_ids` and use `{{{{<sub id>_id}}}}`",
+                    id_attribute.value
+                ),
+            ));
+        }
+    }
+
+    let used: BTreeSet<&str> = parsed.placeholder_names().into_iter().collect();
+    for slot in &attributes.slots {
+        if !used.contains(slot.value().as_str()) {
+            return Err(syn::Error::new(
+                slot.span(),
+                format!("{} never renders the slot `{{{{{{{}}}}}}}`", html.value(), slot.value()),
+            ));
+        }
+    }
+    for (sub_id, placeholder) in attributes.sub_ids.iter().zip(&sub_id_placeholders) {
+        if !used.contains(placeholder.as_str()) {
+            return Err(syn::Error::new(
+                sub_id.span(),
+                format!("{} never uses the sub id `{{{{{}}}}}`", html.value(), placeholder),
+            ));
+        }
+    }
+
+    let mut provided: BTreeSet<String> = BTreeSet::from([String::from("component_id")]);
+    provided.extend(sub_id_placeholders);
+    provided.extend(attributes.slots.iter().map(LitStr::value));
+
+    let slots: BTreeSet<String> = attributes.slots.iter().map(LitStr::value).collect();
+    let mut walker = Walker {
+        html,
+        provided: &provided,
+        slots: &slots,
+        loop_variables: Vec::new(),
+        props_fields: Vec::new(),
+        includes: BTreeSet::new(),
+    };
+    let typed_checks = walker.walk(parsed.nodes())?;
+
+    let mut checked = BTreeSet::new();
+    let field_checks = walker
+        .props_fields
+        .iter()
+        .filter(|(name, _)| checked.insert(name.clone()))
+        .map(|(name, line)| {
+            let message = format!(
+                "{}:{}: placeholder `{}` is not a field of `{}`, an id or a slot of the component",
+                html.value(),
+                line,
+                name,
+                quote!(#props_type)
+            );
+            quote_spanned! {html.span()=>
+                assert!(::pages_components::template::has_field(FIELDS, #name), #message);
+            }
+        });
+
+    Ok(TemplateInfo {
+        checks: quote_spanned! {html.span()=>
+            // Fails to compile when a placeholder isn't a field of the props.
+            const _: () = {
+                const FIELDS: &[&str] = <#props_type as ::pages_components::template::Props>::FIELDS;
+                #(#field_checks)*
+            };
+            // Fails to compile when a loop or an include doesn't fit the props' types.
+            const _: fn(&#props_type) = |props| {
+                let _ = props;
+                #typed_checks
+            };
+        },
+        includes: walker.includes,
+    })
+}
+
+// Walks the template with the loop variables in scope.
+struct Walker<'w> {
+    html: &'w LitStr,
+    provided: &'w BTreeSet<String>,
+    slots: &'w BTreeSet<String>,
+    loop_variables: Vec<&'w str>,
+    // Props fields the template reads, with the line of the first use.
+    props_fields: Vec<(String, usize)>,
+    includes: BTreeSet<(String, bool)>,
+}
+
+impl<'w> Walker<'w> {
+    fn walk(&mut self, nodes: &'w [Node<'w>]) -> syn::Result<TokenStream> {
+        let mut checks = TokenStream::new();
+        for node in nodes {
+            match node {
+                Node::Text(_) => {}
+                Node::Placeholder { name, line, .. } => {
+                    // The children of a slot and their ids would be repeated.
+                    if self.slots.contains(*name) && !self.loop_variables.is_empty() {
+                        return Err(self.error(
+                            *line,
+                            &format!("the slot `{{{{{{{}}}}}}}` can't be rendered inside `{{{{#for}}}}`", name),
+                        ));
+                    }
+                    if !self.provided.contains(*name) {
+                        checks.extend(self.read(name, *line)?);
+                    }
+                }
+                Node::If {
+                    condition,
+                    then,
+                    otherwise,
+                    line,
+                } => {
+                    self.props_only("#if", condition, *line)?;
+                    checks.extend(self.read(condition, *line)?);
+                    checks.extend(self.walk(then)?);
+                    checks.extend(self.walk(otherwise)?);
+                }
+                Node::For {
+                    item,
+                    list,
+                    body,
+                    empty,
+                    line,
+                } => {
+                    self.props_only("#for", list, *line)?;
+                    self.read(list, *line)?;
+                    let list = self.expression(list, *line)?;
+                    let item_ident = syn::parse_str::<Ident>(item)
+                        .map(|ident| Ident::new(&ident.to_string(), self.html.span()))
+                        .map_err(|_| self.error(*line, &format!("`{}` can't name a loop variable", item)))?;
+                    self.loop_variables.push(item);
+                    let body = self.walk(body)?;
+                    self.loop_variables.pop();
+                    let empty = self.walk(empty)?;
+                    checks.extend(quote_spanned! {self.html.span()=>
+                        for #item_ident in &#list {
+                            let _ = #item_ident;
+                            #body
+                        }
+                        #empty
+                    });
+                }
+                Node::Include { component, props, line } => {
+                    let component_ident = syn::parse_str::<Ident>(component)
+                        .map(|ident| Ident::new(&ident.to_string(), self.html.span()))
+                        .map_err(|_| self.error(*line, &format!("`{}` is not a component type", component)))?;
+                    self.includes.insert((component.to_string(), props.is_some()));
+                    match props {
+                        Some(props) => {
+                            self.props_only(">", props, *line)?;
+                            self.read(props, *line)?;
+                            let props = self.expression(props, *line)?;
+                            checks.extend(quote_spanned! {self.html.span()=>
+                                ::pages_components::template::check_include(#component_ident::new, &#props);
+                            });
+                        }
+                        None => checks.extend(quote_spanned! {self.html.span()=>
+                            ::pages_components::template::check_default_include(#component_ident::new);
+                        }),
+                    }
+                }
+            }
+        }
+        Ok(checks)
+    }
+
+    /// Records a read of `path`: a props field for the `FIELDS` check, or a
+    /// field access on a loop variable for the typed check.
+    fn read(&mut self, path: &str, line: usize) -> syn::Result<TokenStream> {
+        let root = path.split('.').next().unwrap_or_default();
+        if !self.loop_variables.contains(&root) {
+            self.props_fields.push((root.to_string(), line));
+            return Ok(TokenStream::new());
+        }
+        if root == path {
+            return Ok(TokenStream::new());
+        }
+        let expression = self.expression(path, line)?;
+        Ok(quote_spanned! {self.html.span()=> let _ = &#expression; })
+    }
+
+    /// `checklist` -> `props.checklist`, `item.text` -> `item.text`.
+    fn expression(&self, path: &str, line: usize) -> syn::Result<TokenStream> {
+        let span = self.html.span();
+        let mut segments = path.split('.');
+        let root = segments.next().unwrap_or_default();
+        let mut expression = if self.loop_variables.contains(&root) {
+            let root = Ident::new(root, span);
+            quote! { #root }
+        } else {
+            let root = member(root, span).ok_or_else(|| self.error(line, &format!("`{}` is not a field name", root)))?;
+            quote_spanned! {span=> props.#root }
+        };
+        for segment in segments {
+            let segment = member(segment, span)
+                .ok_or_else(|| self.error(line, &format!("`{}` is not a field name", segment)))?;
+            expression = quote_spanned! {span=> #expression.#segment };
+        }
+        Ok(expression)
+    }
+
+    /// Blocks decide the shape of the markup before ids and slots are known, so
+    /// they only read the props and loop variables.
+    fn props_only(&self, tag: &str, path: &str, line: usize) -> syn::Result<()> {
+        let root = path.split('.').next().unwrap_or_default();
+        if self.provided.contains(root) && !self.loop_variables.contains(&root) {
+            return Err(self.error(
+                line,
+                &format!("`{{{{{} {}}}}}` can only read props fields and loop variables", tag, path),
+            ));
+        }
+        Ok(())
+    }
+
+    fn error(&self, line: usize, message: &str) -> syn::Error {
+        syn::Error::new(self.html.span(), format!("{}:{}: {}", self.html.value(), line, message))
+    }
+}
+
+fn member(name: &str, span: Span) -> Option<Member> {
+    match syn::parse_str::<Member>(name).ok()? {
+        Member::Named(ident) => Some(Member::Named(Ident::new(&ident.to_string(), span))),
+        Member::Unnamed(index) => Some(Member::Unnamed(syn::Index { index: index.index, span })),
+    }
+}
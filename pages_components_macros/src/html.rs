@@ -0,0 +1,615 @@
+// pages_components_macros/src/html.rs
+
+// Checks the markup of a component template at compile time: every element
+// that isn't void or self-closing must be closed, in order. The `id`
+// attributes are collected on the way so the derive can match them against
+// the component's ids.
+//
+// The check follows the parsed template, so the markup is balanced on every
+// path through its blocks: both branches of an `{{#if}}` must leave the same
+// tags open (`{{#if a}}<div class="a">{{else}}<div class="b">{{/if}}...</div>`
+// is fine, `{{#if a}}<div>{{/if}}` is not), and a `{{#for}}` body must close
+// what it opens. Blocks may sit inside attribute values, except in an `id`.
+//
+// This is not a full HTML parser. It only knows tags, attributes, comments and
+// the raw text of `<script>`/`<style>`; optional end tags (`<li>`, `<p>`) must
+// be written out.
+
+use crate::template::Node;
+
+// Elements that never have content or an end tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+// Elements whose content is text up to their end tag, `<` included.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea", "title"];
+
+/// A problem in the markup and the line it is on.
+pub struct MarkupError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl MarkupError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+/// An `id="..."` attribute and the line it is on.
+pub struct IdAttribute {
+    pub value: String,
+    pub line: usize,
+    /// Whether it is inside a `{{#for}}` body, which repeats it.
+    pub in_loop: bool,
+}
+
+/// Checks that the tags of the template `nodes` (parsed from `source`) are
+/// balanced on every path through its blocks and returns its `id` attributes.
+pub fn check(source: &str, nodes: &[Node]) -> Result<Vec<IdAttribute>, MarkupError> {
+    let mut checker = Checker {
+        source,
+        ids: Vec::new(),
+        loop_depth: 0,
+    };
+    let state = checker.walk(nodes, State::default())?;
+    state.finish()?;
+    Ok(checker.ids)
+}
+
+/// Where the scanner is within the markup.
+#[derive(Debug, Clone, PartialEq)]
+enum Mode {
+    Text,
+    /// Right after a `<`.
+    Open,
+    /// After `<!` and `dashes` dashes: a comment once there are two.
+    Bang { dashes: u8 },
+    /// Inside `<!-- -->`, after `dashes` consecutive dashes.
+    Comment { dashes: u8 },
+    /// `<!DOCTYPE html>`, `<?xml ...>`.
+    Declaration,
+    TagName { name: String },
+    EndTag { name: String, name_done: bool },
+    StartTag { name: String, attribute: Attribute },
+    /// The content of `<script>`, `<style>`...; `matched` bytes of its end tag
+    /// (`</script`) have been seen.
+    RawText { name: String, matched: usize },
+}
+
+/// Where the scanner is within the attributes of a start tag.
+#[derive(Debug, Clone, PartialEq)]
+enum Attribute {
+    /// Between two attributes.
+    None,
+    /// After a `/` that ends the tag if a `>` follows.
+    Slash,
+    Name(String),
+    AfterName(String),
+    BeforeValue(String),
+    Value { name: String, quote: Option<char>, value: String },
+}
+
+/// The scanner's state at one point of the template.
+#[derive(Debug, Clone)]
+struct State {
+    /// Open elements: name and line of their start tag.
+    open: Vec<(String, usize)>,
+    mode: Mode,
+    /// Line where the current tag, comment or raw text started.
+    mode_line: usize,
+    /// Line of the attribute being read.
+    attribute_line: usize,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            open: Vec::new(),
+            mode: Mode::Text,
+            mode_line: 1,
+            attribute_line: 1,
+        }
+    }
+}
+
+impl State {
+    /// Whether both states continue the markup the same way. Lines, the text of
+    /// attribute values and attributes without a value (`{{#if a}}checked{{/if}}`)
+    /// don't matter.
+    fn same_shape(&self, other: &State) -> bool {
+        let names = |state: &State| state.open.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>();
+        let mode = |state: &State| match &state.mode {
+            Mode::StartTag { name, attribute } => Mode::StartTag {
+                name: name.clone(),
+                attribute: match attribute {
+                    Attribute::Name(_) | Attribute::AfterName(_) => Attribute::None,
+                    Attribute::Value { name, quote, .. } => Attribute::Value {
+                        name: name.clone(),
+                        quote: *quote,
+                        value: String::new(),
+                    },
+                    attribute => attribute.clone(),
+                },
+            },
+            mode => mode.clone(),
+        };
+        names(self) == names(other) && mode(self) == mode(other)
+    }
+
+    /// `none`, `` `<ul>`, `<li>` `` or `an unfinished tag`, for error messages.
+    fn describe(&self) -> String {
+        if !matches!(self.mode, Mode::Text | Mode::RawText { .. }) {
+            return String::from("an unfinished tag");
+        }
+        if self.open.is_empty() {
+            return String::from("none");
+        }
+        self.open
+            .iter()
+            .map(|(name, _)| format!("`<{}>`", name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Blocks decide the whole value of an `id`, so they can't build part of it.
+    fn check_block(&self, tag: &str, line: usize) -> Result<(), MarkupError> {
+        match &self.mode {
+            Mode::StartTag {
+                attribute: Attribute::Value { name, .. },
+                ..
+            } if name.eq_ignore_ascii_case("id") => Err(MarkupError::new(
+                line,
+                format!("`{{{{{}}}}}` can't be used inside an `id` attribute", tag),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that nothing is left open at the end of the template.
+    fn finish(mut self) -> Result<(), MarkupError> {
+        let line = self.mode_line;
+        match self.mode {
+            Mode::Text | Mode::Open => {}
+            Mode::Bang { .. } | Mode::Declaration => return Err(MarkupError::new(line, "unclosed declaration")),
+            Mode::Comment { .. } => return Err(MarkupError::new(line, "unclosed comment")),
+            Mode::EndTag { name, .. } => {
+                return Err(MarkupError::new(line, format!("unclosed end tag `</{}`", name)));
+            }
+            Mode::StartTag {
+                attribute: Attribute::Value {
+                    name, quote: Some(_), ..
+                },
+                ..
+            } => {
+                return Err(MarkupError::new(
+                    self.attribute_line,
+                    format!("unclosed value of `{}`", name),
+                ));
+            }
+            Mode::TagName { .. } | Mode::StartTag { .. } => return Err(MarkupError::new(line, "unclosed start tag")),
+            Mode::RawText { name, .. } => {
+                return Err(MarkupError::new(line, format!("`<{}>` is never closed", name)));
+            }
+        }
+        match self.open.pop() {
+            Some((name, line)) => Err(MarkupError::new(line, format!("`<{}>` is never closed", name))),
+            None => Ok(()),
+        }
+    }
+}
+
+struct Checker<'s> {
+    source: &'s str,
+    ids: Vec<IdAttribute>,
+    /// Number of `{{#for}}` bodies around the node being scanned.
+    loop_depth: usize,
+}
+
+impl Checker<'_> {
+    /// Scans `nodes` from `state` and returns the state after them.
+    fn walk(&mut self, nodes: &[Node], mut state: State) -> Result<State, MarkupError> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => {
+                    // Text nodes borrow from the source, which gives their line.
+                    let offset = text.as_ptr() as usize - self.source.as_ptr() as usize;
+                    let line = self.source[..offset].matches('\n').count() + 1;
+                    self.feed(&mut state, text, line)?;
+                }
+                Node::Placeholder { name, raw, line } => {
+                    let placeholder = if *raw {
+                        format!("{{{{{{{}}}}}}}", name)
+                    } else {
+                        format!("{{{{{}}}}}", name)
+                    };
+                    self.feed(&mut state, &placeholder, *line)?;
+                }
+                Node::If {
+                    then, otherwise, line, ..
+                } => {
+                    state.check_block("#if", *line)?;
+                    let after_then = self.walk(then, state.clone())?;
+                    let after_otherwise = self.walk(otherwise, state)?;
+                    if !after_then.same_shape(&after_otherwise) {
+                        return Err(MarkupError::new(
+                            *line,
+                            format!(
+                                "both branches of `{{{{#if}}}}` must leave the same tags open, but one leaves {} and the other {}",
+                                after_then.describe(),
+                                after_otherwise.describe()
+                            ),
+                        ));
+                    }
+                    state = after_then;
+                }
+                Node::For { body, empty, line, .. } => {
+                    state.check_block("#for", *line)?;
+                    // The body may run any number of times, so it must end where it started.
+                    self.loop_depth += 1;
+                    let after_body = self.walk(body, state.clone());
+                    self.loop_depth -= 1;
+                    let after_body = after_body?;
+                    if !after_body.same_shape(&state) {
+                        return Err(MarkupError::new(
+                            *line,
+                            format!(
+                                "the body of `{{{{#for}}}}` must close the tags it opens: it starts with {} open and ends with {}",
+                                state.describe(),
+                                after_body.describe()
+                            ),
+                        ));
+                    }
+                    let after_empty = self.walk(empty, state.clone())?;
+                    if !after_empty.same_shape(&state) {
+                        return Err(MarkupError::new(
+                            *line,
+                            format!(
+                                "the `{{{{else}}}}` of `{{{{#for}}}}` must close the tags it opens: it starts with {} open and ends with {}",
+                                state.describe(),
+                                after_empty.describe()
+                            ),
+                        ));
+                    }
+                }
+                Node::Include { component, line, .. } => {
+                    if !matches!(state.mode, Mode::Text) {
+                        return Err(MarkupError::new(
+                            *line,
+                            format!("`{{{{> {}}}}}` can only be used between tags", component),
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(state)
+    }
+
+    /// Scans `text`, which starts on `line`.
+    fn feed(&mut self, state: &mut State, text: &str, mut line: usize) -> Result<(), MarkupError> {
+        for character in text.chars() {
+            while !self.step(state, character, line)? {}
+            if character == '\n' {
+                line += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the scanner over `character`. Returns `false` when the character
+    /// ended the current construct without being part of it and must be
+    /// scanned again in the new mode.
+    fn step(&mut self, state: &mut State, character: char, line: usize) -> Result<bool, MarkupError> {
+        let lowercase = character.to_ascii_lowercase();
+        match &mut state.mode {
+            Mode::Text => {
+                if character == '<' {
+                    state.mode = Mode::Open;
+                    state.mode_line = line;
+                }
+            }
+            Mode::Open => match character {
+                '/' => {
+                    state.mode = Mode::EndTag {
+                        name: String::new(),
+                        name_done: false,
+                    }
+                }
+                '!' => state.mode = Mode::Bang { dashes: 0 },
+                '?' => state.mode = Mode::Declaration,
+                _ if character.is_ascii_alphabetic() => {
+                    state.mode = Mode::TagName {
+                        name: lowercase.to_string(),
+                    }
+                }
+                // A lone `<` in text.
+                _ => {
+                    state.mode = Mode::Text;
+                    return Ok(false);
+                }
+            },
+            Mode::Bang { dashes } => match (character, *dashes) {
+                ('-', 0) => *dashes = 1,
+                ('-', _) => state.mode = Mode::Comment { dashes: 0 },
+                _ => {
+                    state.mode = Mode::Declaration;
+                    return Ok(false);
+                }
+            },
+            Mode::Comment { dashes } => match character {
+                '-' => *dashes = (*dashes + 1).min(2),
+                '>' if *dashes == 2 => state.mode = Mode::Text,
+                _ => *dashes = 0,
+            },
+            Mode::Declaration => {
+                if character == '>' {
+                    state.mode = Mode::Text;
+                }
+            }
+            Mode::TagName { name } => {
+                if character.is_whitespace() || character == '/' {
+                    state.mode = Mode::StartTag {
+                        name: std::mem::take(name),
+                        attribute: Attribute::None,
+                    };
+                    return Ok(false);
+                } else if character == '>' {
+                    let name = std::mem::take(name);
+                    self.end_start_tag(state, name, false);
+                } else {
+                    name.push(lowercase);
+                }
+            }
+            Mode::EndTag { name, name_done } => {
+                if character == '>' {
+                    let name = std::mem::take(name);
+                    state.mode = Mode::Text;
+                    self.close(state, name)?;
+                } else if character.is_whitespace() || character == '/' {
+                    *name_done = true;
+                } else if !*name_done {
+                    name.push(lowercase);
+                }
+            }
+            Mode::StartTag { name, attribute } => match attribute {
+                Attribute::None => match character {
+                    '>' => {
+                        let name = std::mem::take(name);
+                        self.end_start_tag(state, name, false);
+                    }
+                    '/' => *attribute = Attribute::Slash,
+                    // Whitespace, or a stray `=`.
+                    _ if character.is_whitespace() || character == '=' => {}
+                    _ => {
+                        *attribute = Attribute::Name(character.to_string());
+                        state.attribute_line = line;
+                    }
+                },
+                Attribute::Slash => {
+                    if character == '>' {
+                        let name = std::mem::take(name);
+                        self.end_start_tag(state, name, true);
+                    } else {
+                        *attribute = Attribute::None;
+                        return Ok(false);
+                    }
+                }
+                Attribute::Name(attribute_name) => match character {
+                    '=' => *attribute = Attribute::BeforeValue(std::mem::take(attribute_name)),
+                    '>' | '/' => {
+                        *attribute = Attribute::None;
+                        return Ok(false);
+                    }
+                    _ if character.is_whitespace() => {
+                        *attribute = Attribute::AfterName(std::mem::take(attribute_name));
+                    }
+                    _ => attribute_name.push(character),
+                },
+                Attribute::AfterName(attribute_name) => match character {
+                    '=' => *attribute = Attribute::BeforeValue(std::mem::take(attribute_name)),
+                    _ if character.is_whitespace() => {}
+                    _ => {
+                        *attribute = Attribute::None;
+                        return Ok(false);
+                    }
+                },
+                Attribute::BeforeValue(attribute_name) => match character {
+                    '"' | '\'' => {
+                        *attribute = Attribute::Value {
+                            name: std::mem::take(attribute_name),
+                            quote: Some(character),
+                            value: String::new(),
+                        }
+                    }
+                    '>' => {
+                        *attribute = Attribute::None;
+                        return Ok(false);
+                    }
+                    _ if character.is_whitespace() => {}
+                    _ => {
+                        *attribute = Attribute::Value {
+                            name: std::mem::take(attribute_name),
+                            quote: None,
+                            value: character.to_string(),
+                        }
+                    }
+                },
+                Attribute::Value { name: attribute_name, quote, value } => {
+                    let ends = match quote {
+                        Some(quote) => character == *quote,
+                        None => character.is_whitespace() || character == '>',
+                    };
+                    if !ends {
+                        value.push(character);
+                        return Ok(true);
+                    }
+                    if attribute_name.eq_ignore_ascii_case("id") {
+                        self.ids.push(IdAttribute {
+                            value: std::mem::take(value),
+                            line: state.attribute_line,
+                            in_loop: self.loop_depth > 0,
+                        });
+                    }
+                    let unquoted = quote.is_none();
+                    *attribute = Attribute::None;
+                    // The whitespace or `>` after an unquoted value belongs to the tag.
+                    return Ok(!unquoted);
+                }
+            },
+            Mode::RawText { name, matched } => {
+                let end_tag = format!("</{}", name);
+                if lowercase.is_ascii() && end_tag.as_bytes().get(*matched) == Some(&(lowercase as u8)) {
+                    *matched += 1;
+                    if *matched == end_tag.len() {
+                        // The end tag is then matched like any other.
+                        let name = std::mem::take(name);
+                        state.open.push((name.clone(), state.mode_line));
+                        state.mode = Mode::EndTag { name, name_done: false };
+                        state.mode_line = line;
+                    }
+                } else {
+                    *matched = usize::from(character == '<');
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    fn end_start_tag(&mut self, state: &mut State, name: String, self_closing: bool) {
+        state.mode = Mode::Text;
+        if self_closing || VOID_ELEMENTS.contains(&name.as_str()) {
+            return;
+        }
+        if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+            state.mode = Mode::RawText { name, matched: 0 };
+            return;
+        }
+        state.open.push((name, state.mode_line));
+    }
+
+    fn close(&mut self, state: &mut State, name: String) -> Result<(), MarkupError> {
+        let line = state.mode_line;
+        match state.open.pop() {
+            Some((open_name, _)) if open_name == name => Ok(()),
+            Some((open_name, open_line)) => Err(MarkupError::new(
+                line,
+                format!(
+                    "`</{}>` found while `<{}>` from line {} is still open",
+                    name, open_name, open_line
+                ),
+            )),
+            None => Err(MarkupError::new(line, format!("`</{}>` has no matching start tag", name))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::Template;
+
+    fn check_source(source: &str) -> Result<Vec<String>, String> {
+        let template = Template::parse(source).expect("the test templates parse");
+        check(source, template.nodes())
+            .map(|ids| ids.into_iter().map(|id| id.value).collect())
+            .map_err(|error| format!("{}: {}", error.line, error.message))
+    }
+
+    #[test]
+    fn ids_in_loop_bodies_are_marked() {
+        let source = "<ul id=\"{{component_id}}\">{{#for x in xs}}<li id=\"{{x.id}}\"></li>{{else}}<li id=\"{{body_id}}\"></li>{{/for}}</ul>";
+        let template = Template::parse(source).unwrap();
+        let ids: Vec<(String, bool)> = check(source, template.nodes())
+            .ok()
+            .unwrap()
+            .into_iter()
+            .map(|id| (id.value, id.in_loop))
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                (String::from("{{component_id}}"), false),
+                (String::from("{{x.id}}"), true),
+                (String::from("{{body_id}}"), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn balanced_markup_passes() {
+        assert_eq!(
+            check_source("<div id=\"{{component_id}}\"><br><img src=x /><p>a < b</p><!-- <p> --></div>"),
+            Ok(vec![String::from("{{component_id}}")])
+        );
+        assert!(check_source("<script>if (a < b) { document.write('<p>') }</script>").is_ok());
+        assert!(check_source("<!DOCTYPE html><p>x</p>").is_ok());
+    }
+
+    #[test]
+    fn unbalanced_markup_is_rejected() {
+        assert_eq!(check_source("<div>\n<span></div>"), Err(String::from("2: `</div>` found while `<span>` from line 2 is still open")));
+        assert_eq!(check_source("</p>"), Err(String::from("1: `</p>` has no matching start tag")));
+        assert_eq!(check_source("<ul>\n<li>x</li>"), Err(String::from("1: `<ul>` is never closed")));
+        assert_eq!(check_source("<p title=\"x>y</p>"), Err(String::from("1: unclosed value of `title`")));
+    }
+
+    #[test]
+    fn branches_may_open_the_same_tags_differently() {
+        assert!(check_source("{{#if x}}<div class=\"a\">{{else}}<div class=\"b\">{{/if}}text</div>").is_ok());
+        assert!(check_source("{{#if x}}\n<div\n class=\"a\">{{else}}<div>{{/if}}</div>").is_ok());
+        assert!(check_source("{{#if x}}<b>x</b>{{/if}}").is_ok());
+    }
+
+    #[test]
+    fn branches_must_open_the_same_tags() {
+        assert_eq!(
+            check_source("<p>\n{{#if x}}<div>{{/if}}</p>"),
+            Err(String::from(
+                "2: both branches of `{{#if}}` must leave the same tags open, but one leaves `<p>`, `<div>` and the other `<p>`"
+            ))
+        );
+        assert!(check_source("{{#if x}}<div>{{else}}<span>{{/if}}</div>").is_err());
+        assert!(check_source("{{#if x}}</div>{{/if}}").is_err());
+        // Only the `then` branch is written; the missing `else` can't close anything.
+        assert!(check_source("<div>{{#if x}}</div>{{/if}}").is_err());
+    }
+
+    #[test]
+    fn loop_bodies_must_close_their_tags() {
+        assert!(check_source("<ul>{{#for item in items}}<li>{{item}}</li>{{/for}}</ul>").is_ok());
+        assert_eq!(
+            check_source("<ul>{{#for item in items}}<li>{{/for}}</ul>"),
+            Err(String::from(
+                "1: the body of `{{#for}}` must close the tags it opens: it starts with `<ul>` open and ends with `<ul>`, `<li>`"
+            ))
+        );
+    }
+
+    #[test]
+    fn blocks_may_build_attribute_values_but_not_ids() {
+        assert!(check_source("<li class=\"{{#if done}}done{{else}}todo{{/if}}\">x</li>").is_ok());
+        assert!(check_source("<input {{#if done}}checked{{/if}}>").is_ok());
+        assert_eq!(
+            check_source("<li id=\"{{#if a}}x{{/if}}\"></li>"),
+            Err(String::from("1: `{{#if}}` can't be used inside an `id` attribute"))
+        );
+    }
+
+    #[test]
+    fn ids_of_every_branch_are_collected() {
+        assert_eq!(
+            check_source("{{#if a}}<p id=\"{{header_id}}\"></p>{{else}}<p id='{{body_id}}'></p>{{/if}}"),
+            Ok(vec![String::from("{{header_id}}"), String::from("{{body_id}}")])
+        );
+    }
+
+    #[test]
+    fn includes_go_between_tags() {
+        assert!(check_source("<div>{{> TaskCard001 task}}</div>").is_ok());
+        assert!(check_source("<div {{> TaskCard001}}></div>").is_err());
+    }
+}
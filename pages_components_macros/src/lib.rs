@@ -0,0 +1,370 @@
+// pages_components_macros/src/lib.rs
+
+// `#[derive(Component)]` for the components of `pages_components`.
+//
+// A component is a struct holding its props and its ids; the attribute names
+// its template and assets:
+//
+//     #[derive(Component)]
+//     #[component(
+//         html = "task_card_001/task_card_001.html",
+//         css = ["task_card_001.css"],
+//         js = ["task_card_001.js"],
+//         sub_ids = ["header", "body", "footer"],
+//         slots = ["footer"],
+//     )]
+//     pub struct TaskCard001 {
+//         props: TaskCard001Props,
+//         ids: ComponentIds,
+//         slots: Slots,
+//     }
+//
+// The derive generates `new(props, &mut IdAllocator)`, `props()`,
+// one `with_<slot>(child)` per slot (`with_footer`), and the whole
+// `Component` impl: the template is embedded with `include_str!` and rendered
+// with the props, `{{component_id}}`, one `{{<sub>_id}}` per sub id and one
+// `{{{<slot>}}}` per slot.
+//
+// A template that includes other components (`{{> TaskCard001 task}}`, see
+// `pages_components::template`) needs an `includes: Includes` field. The
+// included components are built by `new`, right after their parent takes its
+// ids, and the type names are resolved where the component is declared.
+//
+// The props type derives `Props` (next to `Serialize`), which lists its field
+// names for the template checks.
+//
+// Mistakes are compile errors instead of render-time panics, and those found in
+// the template name its line (`task_card_001.html:4: ...`): a template or asset
+// file that doesn't exist, a malformed placeholder, an unclosed or mismatched
+// tag, an `id` that isn't `{{component_id}}` or one of the sub ids, an id or
+// slot inside a `{{#for}}` body, a placeholder that is neither a props field
+// nor one of the values above, and a slot the template never renders.
+
+mod attributes;
+mod check;
+mod html;
+mod props;
+mod template;
+
+use std::path::Path;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitStr, Type};
+
+use attributes::ComponentAttributes;
+
+#[proc_macro_derive(Component, attributes(component))]
+pub fn derive_component(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    // Templates and assets are looked up next to the file deriving the component.
+    // `local_file` is relative to rustc's working directory, `include_str!` paths
+    // to the file using the macro; an absolute path suits both.
+    let source_dir = proc_macro::Span::call_site()
+        .local_file()
+        .and_then(|file| std::fs::canonicalize(file).ok())
+        .and_then(|file| file.parent().map(Path::to_path_buf));
+    expand(&input, source_dir.as_deref())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(Props, attributes(serde))]
+pub fn derive_props(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    props::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput, source_dir: Option<&Path>) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+    let attributes = ComponentAttributes::parse(&input.attrs, ident.span())?;
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(ident.span(), "`Component` can only be derived for structs"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new(ident.span(), "`Component` needs a struct with named fields"));
+    };
+    let field = |name: &str| {
+        fields
+            .named
+            .iter()
+            .find(|field| field.ident.as_ref().is_some_and(|ident| ident == name))
+    };
+    let props_type: &Type = &field("props")
+        .ok_or_else(|| syn::Error::new(ident.span(), "a component needs a `props` field with its props type"))?
+        .ty;
+    if field("ids").is_none() {
+        return Err(syn::Error::new(ident.span(), "a component needs an `ids: ComponentIds` field"));
+    }
+    let has_slots = !attributes.slots.is_empty();
+    if has_slots && field("slots").is_none() {
+        return Err(syn::Error::new(
+            ident.span(),
+            "a component with `slots = [...]` needs a `slots: Slots` field",
+        ));
+    }
+
+    let html_path = attributes.html.value();
+    let html_file_name = Path::new(&html_path)
+        .file_name()
+        .map_or(html_path.clone(), |file_name| file_name.to_string_lossy().into_owned());
+    // build.rs scopes the stylesheets of `components/<folder>/` to `<folder>`, so
+    // the name the markup is tagged with must be that folder.
+    let template_folder = source_dir
+        .and_then(|source_dir| std::fs::canonicalize(source_dir.join(&html_path)).ok())
+        .as_deref()
+        .unwrap_or(Path::new(&html_path))
+        .parent()
+        .and_then(Path::file_name)
+        .map(|folder| folder.to_string_lossy().into_owned())
+        .filter(|folder| !folder.is_empty())
+        .ok_or_else(|| syn::Error::new(attributes.html.span(), "the template must sit in the folder of its component"))?;
+    let name = match &attributes.name {
+        Some(name) if name.value() != template_folder => {
+            return Err(syn::Error::new(
+                name.span(),
+                format!(
+                    "the component name must be the folder of its template ({:?}), which its stylesheet is scoped to",
+                    template_folder
+                ),
+            ));
+        }
+        _ => template_folder,
+    };
+
+    // Without a source file (e.g. in some IDE expansions) the checks are skipped
+    // and rustc resolves the template path itself.
+    let template = match source_dir {
+        Some(source_dir) => Some(check::check_template(&attributes, source_dir, props_type)?),
+        None => None,
+    };
+    let template_check = template.as_ref().map(|template| &template.checks);
+
+    // Components included by the template are kept in an `includes: Includes` field.
+    let has_includes = field("includes").is_some();
+    if template.as_ref().is_some_and(|template| !template.includes.is_empty()) && !has_includes {
+        return Err(syn::Error::new(
+            ident.span(),
+            "a component whose template includes other components needs an `includes: Includes` field",
+        ));
+    }
+    // Anything else the struct holds starts out as its default.
+    let other_fields: Vec<&Ident> = fields
+        .named
+        .iter()
+        .filter_map(|field| field.ident.as_ref())
+        .filter(|ident| {
+            !(*ident == "props"
+                || *ident == "ids"
+                || (has_slots && *ident == "slots")
+                || (has_includes && *ident == "includes"))
+        })
+        .collect();
+    let include_path = match source_dir {
+        Some(source_dir) => LitStr::new(
+            &source_dir.join(&html_path).to_string_lossy(),
+            attributes.html.span(),
+        ),
+        None => attributes.html.clone(),
+    };
+
+    let sub_ids = &attributes.sub_ids;
+    let sub_id_placeholders = sub_ids
+        .iter()
+        .map(|sub_id| LitStr::new(&format!("{}_id", sub_id.value()), sub_id.span()));
+    let sub_id_indexes = 0..sub_ids.len();
+    let slots = &attributes.slots;
+    let css = &attributes.css;
+    let js = &attributes.js;
+    let head_js_file_names = match &attributes.head_js {
+        Some(head_js) => quote! {
+            static FILES: ::std::sync::OnceLock<Vec<String>> = ::std::sync::OnceLock::new();
+            Some(FILES.get_or_init(|| vec![#(::pages_components::component::component_asset_name(#name, #head_js)),*]).as_slice())
+        },
+        None => quote! { None },
+    };
+
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let props_expectation = format!("{} should serialize to a struct", quote!(#props_type));
+    let render_expectation = format!("{} should render with the {} context", html_file_name, name);
+
+    // One `with_<slot>` method per slot, so a wrong slot name doesn't compile.
+    let slot_methods = slots
+        .iter()
+        .map(|slot| {
+            let method = syn::parse_str::<Ident>(&format!("with_{}", slot.value()))
+                .map_err(|_| syn::Error::new(slot.span(), "a slot name must be a valid identifier"))?;
+            let doc = format!("Renders `child` in the `{}` slot, after any child already there.", slot.value());
+            Ok(quote! {
+                #[doc = #doc]
+                pub fn #method(mut self, child: impl ::pages_components::Component + 'static) -> Self {
+                    self.slots.insert(#slot, Box::new(child));
+                    self
+                }
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    let slot_items = if has_slots {
+        quote! {
+            /// Named slots of the template that can hold child components.
+            pub const SLOTS: &'static [&'static str] = &[#(#slots),*];
+
+            #(#slot_methods)*
+        }
+    } else {
+        quote! {}
+    };
+    let slots_init = has_slots.then(|| quote! { slots: ::pages_components::Slots::new(), });
+    let includes_init = has_includes.then(|| quote! { includes: ::pages_components::Includes::new(), });
+
+    // Walks the template once at construction to build every included
+    // component with the page's ids, in the order the template renders them.
+    let build_includes = has_includes.then(|| {
+        let arms = template.iter().flat_map(|template| &template.includes).map(|(component, has_props)| {
+            let component_ident = Ident::new(component, attributes.html.span());
+            if *has_props {
+                let props_message = format!(
+                    "{}:{{}}: the props of `{{{{> {} ...}}}}` don't fit {}: {{}}",
+                    html_path, component, component
+                );
+                quote! {
+                    (#component, Some(props)) => Box::new(#component_ident::new(
+                        props.deserialize().unwrap_or_else(|error| panic!(#props_message, line, error)),
+                        ids,
+                    )),
+                }
+            } else {
+                quote! {
+                    (#component, None) => Box::new(#component_ident::new(::core::default::Default::default(), ids)),
+                }
+            }
+        });
+        quote! {
+            let mut included: Vec<Box<dyn ::pages_components::Component>> = Vec::new();
+            ::pages_components::template::Template::parse(Self::TEMPLATE)
+                .and_then(|template| {
+                    ::pages_components::template::render_with_includes(&template, &component.template_context(), &mut |name, props, line| {
+                        included.push(match (name, props) {
+                            #(#arms)*
+                            _ => unreachable!("{} doesn't include {}", #html_file_name, name),
+                        });
+                        Ok(String::new())
+                    })
+                })
+                .expect(#render_expectation);
+            for child in included {
+                component.includes.push(child);
+            }
+        }
+    });
+    let render = if has_includes {
+        quote! {
+            let mut included = self.includes.children().into_iter();
+            let markup = ::pages_components::template::Template::parse(Self::TEMPLATE)
+                .and_then(|template| {
+                    ::pages_components::template::render_with_includes(&template, &self.template_context(), &mut |_, _, _| {
+                        Ok(included.next().map(|child| child.print_component_markup()).unwrap_or_default())
+                    })
+                })
+                .expect(#render_expectation);
+        }
+    } else {
+        quote! {
+            let markup = ::pages_components::template::render(Self::TEMPLATE, &self.template_context())
+                .expect(#render_expectation);
+        }
+    };
+
+    let component_binding = if has_includes {
+        quote! { mut component }
+    } else {
+        quote! { component }
+    };
+    let slot_children = has_slots.then(|| quote! { children.extend(self.slots.children()); });
+    let included_children = has_includes.then(|| quote! { children.extend(self.includes.children()); });
+    let children = (has_slots || has_includes).then(|| {
+        quote! {
+            fn children(&self) -> Vec<&dyn ::pages_components::Component> {
+                let mut children = Vec::new();
+                #slot_children
+                #included_children
+                children
+            }
+        }
+    });
+
+    Ok(quote! {
+        #template_check
+
+        impl #impl_generics #ident #type_generics #where_clause {
+            /// Folder name of the component; its stylesheet is scoped to it at
+            /// build time and its instance ids start with it.
+            pub const COMPONENT_NAME: &'static str = #name;
+
+            const TEMPLATE: &'static str = include_str!(#include_path);
+
+            /// Builds the component with the page's id allocator, which hands it
+            /// the next `<name>-N` id.
+            pub fn new(props: #props_type, ids: &mut ::pages_components::IdAllocator) -> Self {
+                let #component_binding = Self {
+                    props,
+                    ids: ids.allocate(Self::COMPONENT_NAME, &[#(#sub_ids),*]),
+                    #slots_init
+                    #includes_init
+                    #(#other_fields: ::core::default::Default::default(),)*
+                };
+                #build_includes
+                component
+            }
+
+            pub fn props(&self) -> &#props_type {
+                &self.props
+            }
+
+            #slot_items
+
+            fn template_context(&self) -> ::pages_components::template::Context {
+                let mut context = ::pages_components::template::Context::from_props(&self.props)
+                    .expect(#props_expectation);
+                context.insert("component_id", &self.ids.id);
+                #(context.insert(#sub_id_placeholders, &self.ids.sub_ids[#sub_id_indexes]);)*
+                #(context.insert(#slots, self.slots.render(#slots));)*
+                context
+            }
+        }
+
+        impl #impl_generics ::pages_components::Component for #ident #type_generics #where_clause {
+            fn print_component_markup(&self) -> String {
+                #render
+                ::pages_components::component::scope_root_element(&markup, Self::COMPONENT_NAME)
+            }
+
+            fn css_file_names(&self) -> &[String] {
+                static FILES: ::std::sync::OnceLock<Vec<String>> = ::std::sync::OnceLock::new();
+                FILES.get_or_init(|| vec![#(::pages_components::component::component_asset_name(#name, #css)),*])
+            }
+
+            fn js_file_names(&self) -> &[String] {
+                static FILES: ::std::sync::OnceLock<Vec<String>> = ::std::sync::OnceLock::new();
+                FILES.get_or_init(|| vec![#(::pages_components::component::component_asset_name(#name, #js)),*])
+            }
+
+            fn head_js_file_names(&self) -> Option<&[String]> {
+                #head_js_file_names
+            }
+
+            fn print_component_ids(&self) -> String {
+                self.ids.id.clone()
+            }
+
+            fn sub_component_ids(&self) -> &[String] {
+                &self.ids.sub_ids
+            }
+
+            #children
+        }
+    })
+}
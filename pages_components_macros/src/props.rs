@@ -0,0 +1,79 @@
+// pages_components_macros/src/props.rs
+
+// `#[derive(Props)]`: lists the field names of a props struct as
+// `pages_components::template::Props::FIELDS`, under the names serde gives
+// them, so component templates can be checked against them at compile time.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Attribute, Data, DeriveInput, Fields, LitStr};
+
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(ident.span(), "`Props` can only be derived for structs"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new(ident.span(), "`Props` needs a struct with named fields"));
+    };
+    if let Some(rename_all) = serde_option(&input.attrs, "rename_all")? {
+        return Err(syn::Error::new(
+            rename_all.span(),
+            "`Props` doesn't support `#[serde(rename_all)]`; rename the fields one by one",
+        ));
+    }
+
+    let mut names = Vec::new();
+    for field in &fields.named {
+        let is_skipped = serde_option(&field.attrs, "skip")?.is_some()
+            || serde_option(&field.attrs, "skip_serializing")?.is_some();
+        if is_skipped {
+            continue;
+        }
+        let name = match serde_option(&field.attrs, "rename")? {
+            Some(rename) => rename.value(),
+            None => field
+                .ident
+                .as_ref()
+                .map(|ident| ident.to_string().trim_start_matches("r#").to_string())
+                .unwrap_or_default(),
+        };
+        names.push(name);
+    }
+
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::pages_components::template::Props for #ident #type_generics #where_clause {
+            const FIELDS: &'static [&'static str] = &[#(#names),*];
+        }
+    })
+}
+
+/// The `#[serde(...)]` option `key` of `attributes`: its value for
+/// `key = "..."`, the key itself for a flag such as `skip`.
+fn serde_option(attributes: &[Attribute], key: &str) -> syn::Result<Option<LitStr>> {
+    let mut found = None;
+    for attribute in attributes.iter().filter(|attribute| attribute.path().is_ident("serde")) {
+        attribute.parse_nested_meta(|meta| {
+            let is_key = meta.path.is_ident(key);
+            if meta.input.peek(syn::Token![=]) {
+                let value = meta.value()?;
+                if is_key {
+                    found = Some(value.parse::<LitStr>()?);
+                } else {
+                    value.parse::<syn::Expr>()?;
+                }
+            } else if meta.input.peek(syn::token::Paren) {
+                // `rename(serialize = "...")` and the like.
+                let content;
+                syn::parenthesized!(content in meta.input);
+                content.parse::<TokenStream>()?;
+            } else if is_key {
+                found = Some(LitStr::new(key, meta.path.span()));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(found)
+}
@@ -0,0 +1,18 @@
+// pages_components_macros/src/template.rs
+
+// Component templates as the runtime reads them: the parser is the one
+// `pages_components::template` renders with, from `pages_template_syntax`, so
+// both always agree on the syntax.
+
+pub use pages_template_syntax::{Node, Template};
+
+/// The name inside `value` when it is exactly one escaped placeholder (`{{ header_id }}`).
+pub fn single_placeholder(value: &str) -> Option<&str> {
+    let inner = value.trim().strip_prefix("{{")?.strip_suffix("}}")?;
+    let name = inner.trim();
+    let is_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || character == '_');
+    (!inner.starts_with('{') && is_name).then_some(name)
+}
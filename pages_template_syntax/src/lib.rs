@@ -0,0 +1,508 @@
+// pages_template_syntax/src/lib.rs
+
+// Parses component templates into a tree of nodes (see
+// `pages_components::template` for the syntax).
+//
+// `pages_components` renders the tree and `pages_components_macros` checks it
+// at compile time, so both read templates with exactly the same parser. The
+// crate only uses `std`, which keeps the proc-macro build light.
+
+use std::fmt;
+
+/// Errors produced while parsing or rendering a template.
+/// `line` is 1-based and points into the template source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// A `{{` or `{{{` without its closing braces.
+    UnclosedPlaceholder { line: usize },
+    /// A placeholder with nothing (or something that isn't a name) inside.
+    InvalidPlaceholder { name: String, line: usize },
+    /// A `{{#if}}` or `{{#for}}` without its `{{/if}}` or `{{/for}}`.
+    UnclosedBlock { tag: String, line: usize },
+    /// An `{{else}}`, `{{/if}}` or `{{/for}}` that doesn't close an open block.
+    UnexpectedTag { tag: String, line: usize },
+    /// A placeholder whose name has no value in the `Context`.
+    MissingValue { name: String, line: usize },
+    /// A list or struct used where text is expected.
+    NotText { name: String, line: usize },
+    /// A `{{#for}}` over something that isn't a list.
+    NotIterable { name: String, line: usize },
+    /// A `{{> Component}}` in a template rendered without components to include.
+    UnexpectedInclude { component: String, line: usize },
+    /// Props that don't serialize to a struct/map.
+    UnsupportedProps,
+}
+
+impl TemplateError {
+    /// The template line the error is about, if any.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            TemplateError::UnclosedPlaceholder { line }
+            | TemplateError::InvalidPlaceholder { line, .. }
+            | TemplateError::UnclosedBlock { line, .. }
+            | TemplateError::UnexpectedTag { line, .. }
+            | TemplateError::MissingValue { line, .. }
+            | TemplateError::NotText { line, .. }
+            | TemplateError::NotIterable { line, .. }
+            | TemplateError::UnexpectedInclude { line, .. } => Some(*line),
+            TemplateError::UnsupportedProps => None,
+        }
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::UnclosedPlaceholder { line } => {
+                write!(f, "line {}: unclosed placeholder", line)
+            }
+            TemplateError::InvalidPlaceholder { name, line } => {
+                write!(f, "line {}: invalid placeholder `{}`", line, name)
+            }
+            TemplateError::UnclosedBlock { tag, line } => {
+                write!(f, "line {}: `{{{{{}}}}}` is never closed", line, tag)
+            }
+            TemplateError::UnexpectedTag { tag, line } => {
+                write!(f, "line {}: `{{{{{}}}}}` doesn't close an open block", line, tag)
+            }
+            TemplateError::MissingValue { name, line } => {
+                write!(f, "line {}: no value for placeholder `{}`", line, name)
+            }
+            TemplateError::NotText { name, line } => {
+                write!(f, "line {}: `{}` is a list or struct, not text", line, name)
+            }
+            TemplateError::NotIterable { name, line } => {
+                write!(f, "line {}: `{}` is not a list", line, name)
+            }
+            TemplateError::UnexpectedInclude { component, line } => {
+                write!(f, "line {}: `{}` can't be included here", line, component)
+            }
+            TemplateError::UnsupportedProps => {
+                write!(f, "props must serialize to a struct or map")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// One piece of a parsed template. Names may be dotted (`item.text`) to read
+/// the field of a struct.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node<'a> {
+    /// Literal markup, copied to the output as is.
+    Text(&'a str),
+    /// A `{{ name }}` (escaped) or `{{{ name }}}` (raw) placeholder.
+    Placeholder { name: &'a str, raw: bool, line: usize },
+    /// `{{#if condition}} then {{else}} otherwise {{/if}}`.
+    If {
+        condition: &'a str,
+        then: Vec<Node<'a>>,
+        otherwise: Vec<Node<'a>>,
+        line: usize,
+    },
+    /// `{{#for item in list}} body {{else}} empty {{/for}}`.
+    For {
+        item: &'a str,
+        list: &'a str,
+        body: Vec<Node<'a>>,
+        empty: Vec<Node<'a>>,
+        line: usize,
+    },
+    /// `{{> Component props}}`: another component, built with `props` (or its
+    /// default props when omitted).
+    Include {
+        component: &'a str,
+        props: Option<&'a str>,
+        line: usize,
+    },
+}
+
+/// A parsed template, borrowing from its source.
+#[derive(Debug, Clone)]
+pub struct Template<'a> {
+    nodes: Vec<Node<'a>>,
+}
+
+// A block being parsed, with the nodes around it.
+struct OpenBlock<'a> {
+    tag: &'a str,
+    line: usize,
+    kind: BlockKind<'a>,
+    outer: Vec<Node<'a>>,
+}
+
+enum BlockKind<'a> {
+    // `then` and `body` are set once `{{else}}` is reached.
+    If {
+        condition: &'a str,
+        then: Option<Vec<Node<'a>>>,
+    },
+    For {
+        item: &'a str,
+        list: &'a str,
+        body: Option<Vec<Node<'a>>>,
+    },
+}
+
+impl<'a> Template<'a> {
+    /// Parses `source` into literal text, placeholders and blocks.
+    pub fn parse(source: &'a str) -> Result<Self, TemplateError> {
+        // Nodes of the innermost open block (or of the template).
+        let mut nodes = Vec::new();
+        let mut open_blocks: Vec<OpenBlock<'a>> = Vec::new();
+        let mut rest = source;
+        let mut line = 1;
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                nodes.push(Node::Text(&rest[..start]));
+            }
+            line += rest[..start].matches('\n').count();
+
+            let raw = rest[start..].starts_with("{{{");
+            let (open, close) = if raw { ("{{{", "}}}") } else { ("{{", "}}") };
+            let after_open = &rest[start + open.len()..];
+            let end = after_open
+                .find(close)
+                .ok_or(TemplateError::UnclosedPlaceholder { line })?;
+
+            let inner = &after_open[..end];
+            let tag = inner.trim();
+            let invalid = || TemplateError::InvalidPlaceholder {
+                name: inner.to_string(),
+                line,
+            };
+
+            if raw {
+                if !is_valid_path(tag) {
+                    return Err(invalid());
+                }
+                nodes.push(Node::Placeholder { name: tag, raw, line });
+            } else if let Some(condition) = block_argument(tag, "#if") {
+                let condition = condition.trim();
+                if !is_valid_path(condition) {
+                    return Err(invalid());
+                }
+                open_blocks.push(OpenBlock {
+                    tag,
+                    line,
+                    kind: BlockKind::If { condition, then: None },
+                    outer: std::mem::take(&mut nodes),
+                });
+            } else if let Some(loop_header) = block_argument(tag, "#for") {
+                let mut words = loop_header.split_whitespace();
+                let (Some(item), Some("in"), Some(list), None) = (words.next(), words.next(), words.next(), words.next())
+                else {
+                    return Err(invalid());
+                };
+                if !is_valid_name(item) || !is_valid_path(list) {
+                    return Err(invalid());
+                }
+                open_blocks.push(OpenBlock {
+                    tag,
+                    line,
+                    kind: BlockKind::For { item, list, body: None },
+                    outer: std::mem::take(&mut nodes),
+                });
+            } else if tag == "else" {
+                match open_blocks.last_mut() {
+                    Some(OpenBlock {
+                        kind: BlockKind::If { then: before_else @ None, .. } | BlockKind::For { body: before_else @ None, .. },
+                        ..
+                    }) => *before_else = Some(std::mem::take(&mut nodes)),
+                    _ => {
+                        return Err(TemplateError::UnexpectedTag {
+                            tag: tag.to_string(),
+                            line,
+                        });
+                    }
+                }
+            } else if tag == "/if" || tag == "/for" {
+                let block = match open_blocks.pop() {
+                    Some(block) if matches!(
+                        (&block.kind, tag),
+                        (BlockKind::If { .. }, "/if") | (BlockKind::For { .. }, "/for")
+                    ) => block,
+                    // `{{#for}} ... {{/if}}`: the block is the mistake, not the end tag.
+                    Some(block) => {
+                        return Err(TemplateError::UnclosedBlock {
+                            tag: block.tag.to_string(),
+                            line: block.line,
+                        });
+                    }
+                    None => {
+                        return Err(TemplateError::UnexpectedTag {
+                            tag: tag.to_string(),
+                            line,
+                        });
+                    }
+                };
+                let inner_nodes = std::mem::replace(&mut nodes, block.outer);
+                nodes.push(match block.kind {
+                    BlockKind::If { condition, then: None } => Node::If {
+                        condition,
+                        then: inner_nodes,
+                        otherwise: Vec::new(),
+                        line: block.line,
+                    },
+                    BlockKind::If {
+                        condition,
+                        then: Some(then),
+                    } => Node::If {
+                        condition,
+                        then,
+                        otherwise: inner_nodes,
+                        line: block.line,
+                    },
+                    BlockKind::For { item, list, body: None } => Node::For {
+                        item,
+                        list,
+                        body: inner_nodes,
+                        empty: Vec::new(),
+                        line: block.line,
+                    },
+                    BlockKind::For {
+                        item,
+                        list,
+                        body: Some(body),
+                    } => Node::For {
+                        item,
+                        list,
+                        body,
+                        empty: inner_nodes,
+                        line: block.line,
+                    },
+                });
+            } else if let Some(include) = tag.strip_prefix('>') {
+                let mut words = include.split_whitespace();
+                let (Some(component), props, None) = (words.next(), words.next(), words.next()) else {
+                    return Err(invalid());
+                };
+                let is_type_name = component.starts_with(|character: char| character.is_ascii_alphabetic());
+                if !(is_type_name && is_valid_name(component) && props.is_none_or(is_valid_path)) {
+                    return Err(invalid());
+                }
+                nodes.push(Node::Include { component, props, line });
+            } else {
+                if !is_valid_path(tag) {
+                    return Err(invalid());
+                }
+                nodes.push(Node::Placeholder { name: tag, raw, line });
+            }
+
+            line += inner.matches('\n').count();
+            rest = &after_open[end + close.len()..];
+        }
+
+        if let Some(block) = open_blocks.pop() {
+            return Err(TemplateError::UnclosedBlock {
+                tag: block.tag.to_string(),
+                line: block.line,
+            });
+        }
+        if !rest.is_empty() {
+            nodes.push(Node::Text(rest));
+        }
+
+        Ok(Self { nodes })
+    }
+
+    pub fn nodes(&self) -> &[Node<'a>] {
+        &self.nodes
+    }
+
+    /// Names of all placeholders used by the template, blocks included, in
+    /// order of appearance.
+    pub fn placeholder_names(&self) -> Vec<&'a str> {
+        fn collect<'a>(nodes: &[Node<'a>], names: &mut Vec<&'a str>) {
+            for node in nodes {
+                match node {
+                    Node::Placeholder { name, .. } => names.push(name),
+                    Node::If { then, otherwise, .. } => {
+                        collect(then, names);
+                        collect(otherwise, names);
+                    }
+                    Node::For { body, empty, .. } => {
+                        collect(body, names);
+                        collect(empty, names);
+                    }
+                    Node::Text(_) | Node::Include { .. } => {}
+                }
+            }
+        }
+        let mut names = Vec::new();
+        collect(&self.nodes, &mut names);
+        names
+    }
+}
+
+/// What follows `keyword` in `tag` (`done` in `#if done`), when whitespace separates them.
+fn block_argument<'t>(tag: &'t str, keyword: &str) -> Option<&'t str> {
+    tag.strip_prefix(keyword)
+        .filter(|argument| argument.starts_with(char::is_whitespace))
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || character == '_')
+}
+
+/// `name` or `name.field.field`.
+fn is_valid_path(path: &str) -> bool {
+    path.split('.').all(is_valid_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Vec<Node<'_>> {
+        Template::parse(source).expect("the template parses").nodes
+    }
+
+    #[test]
+    fn text_and_placeholders() {
+        assert_eq!(
+            parse("a {{ b }} {{{c.d}}}"),
+            vec![
+                Node::Text("a "),
+                Node::Placeholder { name: "b", raw: false, line: 1 },
+                Node::Text(" "),
+                Node::Placeholder { name: "c.d", raw: true, line: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn blocks_nest() {
+        assert_eq!(
+            parse("{{#if a}}{{#for x in xs}}{{#if x.b}}{{x.c}}{{/if}}{{/for}}{{else}}-{{/if}}"),
+            vec![Node::If {
+                condition: "a",
+                then: vec![Node::For {
+                    item: "x",
+                    list: "xs",
+                    body: vec![Node::If {
+                        condition: "x.b",
+                        then: vec![Node::Placeholder { name: "x.c", raw: false, line: 1 }],
+                        otherwise: vec![],
+                        line: 1,
+                    }],
+                    empty: vec![],
+                    line: 1,
+                }],
+                otherwise: vec![Node::Text("-")],
+                line: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn else_splits_the_innermost_block() {
+        assert_eq!(
+            parse("{{#for x in xs}}{{#if x}}1{{else}}2{{/if}}{{else}}3{{/for}}"),
+            vec![Node::For {
+                item: "x",
+                list: "xs",
+                body: vec![Node::If {
+                    condition: "x",
+                    then: vec![Node::Text("1")],
+                    otherwise: vec![Node::Text("2")],
+                    line: 1,
+                }],
+                empty: vec![Node::Text("3")],
+                line: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn blocks_accept_any_whitespace() {
+        assert_eq!(parse("{{#if\tdone}}x{{/if}}"), parse("{{#if done}}x{{/if}}"));
+        assert_eq!(parse("{{ #for\n  item\tin  items }}x{{ /for }}")[0], Node::For {
+            item: "item",
+            list: "items",
+            body: vec![Node::Text("x")],
+            empty: vec![],
+            line: 1,
+        });
+        assert_eq!(
+            Template::parse("{{#ifdone}}x{{/if}}").unwrap_err(),
+            TemplateError::InvalidPlaceholder { name: String::from("#ifdone"), line: 1 }
+        );
+    }
+
+    #[test]
+    fn lines_count_from_one() {
+        assert_eq!(
+            parse("a\n{{#if b}}\n{{c}}{{/if}}"),
+            vec![
+                Node::Text("a\n"),
+                Node::If {
+                    condition: "b",
+                    then: vec![Node::Text("\n"), Node::Placeholder { name: "c", raw: false, line: 3 }],
+                    otherwise: vec![],
+                    line: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn includes_take_optional_props() {
+        assert_eq!(
+            parse("{{> TaskCard001 task}}{{>TaskCard001}}"),
+            vec![
+                Node::Include { component: "TaskCard001", props: Some("task"), line: 1 },
+                Node::Include { component: "TaskCard001", props: None, line: 1 },
+            ]
+        );
+        assert!(Template::parse("{{> taskCard}}").is_ok());
+        assert!(Template::parse("{{> 1Card}}").is_err());
+        assert!(Template::parse("{{> Card a b}}").is_err());
+    }
+
+    #[test]
+    fn misplaced_block_tags_are_errors() {
+        assert_eq!(
+            Template::parse("{{else}}").unwrap_err(),
+            TemplateError::UnexpectedTag { tag: String::from("else"), line: 1 }
+        );
+        assert_eq!(
+            Template::parse("{{#if a}}{{else}}{{else}}{{/if}}").unwrap_err(),
+            TemplateError::UnexpectedTag { tag: String::from("else"), line: 1 }
+        );
+        assert_eq!(
+            Template::parse("\n{{/for}}").unwrap_err(),
+            TemplateError::UnexpectedTag { tag: String::from("/for"), line: 2 }
+        );
+        assert_eq!(
+            Template::parse("{{#for x in xs}}\n{{#if x}}{{/for}}").unwrap_err(),
+            TemplateError::UnclosedBlock { tag: String::from("#if x"), line: 2 }
+        );
+        assert_eq!(
+            Template::parse("{{#if a}}\n").unwrap_err(),
+            TemplateError::UnclosedBlock { tag: String::from("#if a"), line: 1 }
+        );
+    }
+
+    #[test]
+    fn malformed_block_headers_are_errors() {
+        for source in ["{{#if}}", "{{#if a b}}{{/if}}", "{{#for x xs}}{{/for}}", "{{#for x in}}{{/for}}", "{{#for x.y in xs}}{{/for}}"] {
+            assert!(
+                matches!(Template::parse(source), Err(TemplateError::InvalidPlaceholder { .. })),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn placeholder_names_cover_every_block() {
+        let template = Template::parse("{{a}}{{#if b}}{{c}}{{else}}{{d}}{{/if}}{{#for x in e}}{{x.f}}{{else}}{{g}}{{/for}}").unwrap();
+        assert_eq!(template.placeholder_names(), vec!["a", "c", "d", "x.f", "g"]);
+    }
+}
@@ -0,0 +1,61 @@
+// src/asset_reader.rs
+
+//! Reads staged assets for the renderer (critical CSS inlining, bundles).
+//!
+//! Files are looked up by their logical name, resolved through the asset
+//! manifest like their tags are, and read from the static folder or, in embed
+//! mode, from the binary. The staged assets don't change while the server runs,
+//! so each file is read once and kept in memory.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use pages_components::assets::{AssetContext, AssetManifest, AssetReader};
+
+pub struct StaticAssetReader {
+    /// Resolves the URLs the files are read from.
+    assets: AssetContext,
+    static_dir: PathBuf,
+    cache: RwLock<HashMap<String, Arc<str>>>,
+}
+
+impl StaticAssetReader {
+    /// `static_dir` is where the staged assets are read from (unused in embed mode).
+    pub fn new(manifest: Arc<AssetManifest>, static_dir: PathBuf) -> Self {
+        Self {
+            assets: AssetContext::new(manifest),
+            static_dir,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn load(&self, served_name: &str) -> Option<Vec<u8>> {
+        #[cfg(feature = "embed")]
+        let contents = crate::embedded::contents(served_name).map(<[u8]>::to_vec);
+        #[cfg(not(feature = "embed"))]
+        let contents = std::fs::read(self.static_dir.join(served_name)).ok();
+        contents
+    }
+}
+
+impl AssetReader for StaticAssetReader {
+    fn read(&self, file_name: &str) -> Option<Arc<str>> {
+        if let Some(contents) = self.cache.read().ok()?.get(file_name) {
+            return Some(Arc::clone(contents));
+        }
+
+        let url = self.assets.url(file_name);
+        let served_name = url.trim_start_matches('/');
+        let Some(contents) = self.load(served_name) else {
+            tracing::warn!("Asset {:?} not found at {:?} in {:?}", file_name, served_name, self.static_dir);
+            return None;
+        };
+        let contents: Arc<str> = String::from_utf8_lossy(&contents).into();
+        self.cache
+            .write()
+            .ok()?
+            .insert(file_name.to_string(), Arc::clone(&contents));
+        Some(contents)
+    }
+}
@@ -0,0 +1,237 @@
+// src/bundles.rs
+
+//! Per-page asset bundles (`bundle_assets = true`, see `config`).
+//!
+//! The first render of a page asks for one bundle per tag group: its
+//! stylesheets, its head scripts and its bottom scripts. The files are read
+//! through the `AssetReader` (see `asset_reader`), joined in the
+//! order the tags would have loaded them, and kept in memory under a
+//! content-hashed name. Later renders with the same files get the same URL
+//! without rebuilding the bundle.
+//!
+//! The URL also lists the files, as in
+//! `/bundles/3f9a1c2b7e0d4a51.css?files=global.css,home_page.css`, so any
+//! instance running the same build can serve it, including one that restarted
+//! since the page was rendered: an unknown bundle is rebuilt from that list and
+//! served when its hash matches the name. Only files of the asset manifest with
+//! the bundle's extension are accepted. Bundles rebuilt this way aren't kept, so
+//! made-up file lists can't fill the memory.
+//!
+//! Bundles never change under the same name, so they are cached for a year.
+//! Compression is left to the dynamic compression layer.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use pages_components::assets::{strip_source_map_comments, AssetBundler, AssetContext, AssetKind};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::static_files::{etag_matches, IMMUTABLE_CACHE_CONTROL};
+
+/// URL prefix the bundles are served under.
+pub const BUNDLE_ROUTE_PREFIX: &str = "/bundles/";
+
+// Hex characters of the content hash used as the bundle name.
+const BUNDLE_HASH_LENGTH: usize = 16;
+
+struct Bundle {
+    kind: AssetKind,
+    contents: Vec<u8>,
+    etag: String,
+}
+
+/// Builds bundles on demand and serves them from memory.
+pub struct Bundler {
+    /// Lists the files a bundle may hold and reads them.
+    assets: AssetContext,
+    /// Bundle name (`3f9a1c2b7e0d4a51.css`) -> bundle.
+    bundles: RwLock<HashMap<String, Arc<Bundle>>>,
+    /// Files of a tag group -> URL of their bundle.
+    urls: RwLock<HashMap<(AssetKind, Vec<String>), String>>,
+}
+
+impl Bundler {
+    /// `assets` needs a reader; its own bundler, if any, is not used.
+    pub fn new(assets: AssetContext) -> Self {
+        Self {
+            assets,
+            bundles: RwLock::new(HashMap::new()),
+            urls: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Whether `file_name` may go into a bundle of `kind` and be listed in its URL.
+    fn accepts(&self, kind: AssetKind, file_name: &str) -> bool {
+        let safe = !file_name.is_empty()
+            && !file_name.split('/').any(|part| part.is_empty() || part == "..")
+            && file_name
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'_' | b'-' | b'/'));
+        safe && file_name.ends_with(&format!(".{}", extension(kind))) && self.assets.manifest().get(file_name).is_some()
+    }
+
+    fn build(&self, kind: AssetKind, file_names: &[&str]) -> Option<(String, Bundle)> {
+        if !file_names.iter().all(|file_name| self.accepts(kind, file_name)) {
+            return None;
+        }
+
+        let mut contents = Vec::new();
+        for file_name in file_names {
+            let asset = self.assets.read(file_name)?;
+            contents.extend_from_slice(format!("/* {} */\n", file_name).as_bytes());
+            contents.extend_from_slice(strip_source_map_comments(&asset).as_bytes());
+            if kind == AssetKind::Script {
+                // A file without a trailing semicolon must not run into the next one.
+                contents.extend_from_slice(b";\n");
+            }
+        }
+
+        let hash: String = Sha256::digest(&contents)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>()[..BUNDLE_HASH_LENGTH]
+            .to_string();
+        let name = format!("{}.{}", hash, extension(kind));
+        let bundle = Bundle {
+            kind,
+            contents,
+            etag: format!("\"{}\"", hash),
+        };
+        Some((name, bundle))
+    }
+
+    /// Rebuilds the bundle `name` from the `files` list of its URL, if that
+    /// list is valid and still produces `name`.
+    fn rebuild(&self, name: &str, files: &str) -> Option<Bundle> {
+        let kind = match name.rsplit_once('.')?.1 {
+            "css" => AssetKind::Style,
+            "js" => AssetKind::Script,
+            _ => return None,
+        };
+        let file_names: Vec<&str> = files.split(',').collect();
+        let (rebuilt_name, bundle) = self.build(kind, &file_names)?;
+        if rebuilt_name != name {
+            tracing::debug!("Bundle {} of {:?} now hashes to {}", name, file_names, rebuilt_name);
+            return None;
+        }
+        Some(bundle)
+    }
+}
+
+fn extension(kind: AssetKind) -> &'static str {
+    match kind {
+        AssetKind::Style => "css",
+        AssetKind::Script => "js",
+    }
+}
+
+impl AssetBundler for Bundler {
+    fn bundle_url(&self, kind: AssetKind, file_names: &[&str]) -> Option<String> {
+        let key = (kind, file_names.iter().map(|file_name| file_name.to_string()).collect::<Vec<_>>());
+        if let Some(url) = self.urls.read().ok()?.get(&key) {
+            return Some(url.clone());
+        }
+
+        let (name, bundle) = self.build(kind, file_names)?;
+        let url = format!("{}{}?files={}", BUNDLE_ROUTE_PREFIX, name, file_names.join(","));
+        tracing::debug!("Bundled {:?} into {}", file_names, url);
+        self.bundles.write().ok()?.entry(name).or_insert_with(|| Arc::new(bundle));
+        self.urls.write().ok()?.insert(key, url.clone());
+        Some(url)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BundleQuery {
+    /// Comma-separated logical names of the bundled files.
+    files: Option<String>,
+}
+
+/// Serves `GET /bundles/{name}?files=...`.
+pub async fn serve(
+    State(bundler): State<Arc<Bundler>>,
+    Path(name): Path<String>,
+    Query(query): Query<BundleQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let cached = bundler
+        .bundles
+        .read()
+        .ok()
+        .and_then(|bundles| bundles.get(&name).cloned());
+    let Some(bundle) = cached.or_else(|| {
+        let files = query.files.as_deref()?;
+        bundler.rebuild(&name, files).map(Arc::new)
+    }) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let etag = HeaderValue::from_str(&bundle.etag).expect("bundle ETags are hex strings");
+    let cache_control = HeaderValue::from_static(IMMUTABLE_CACHE_CONTROL);
+    if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH)
+        && etag_matches(if_none_match, &etag)
+    {
+        let mut response = StatusCode::NOT_MODIFIED.into_response();
+        response.headers_mut().insert(header::ETAG, etag);
+        response.headers_mut().insert(header::CACHE_CONTROL, cache_control);
+        return response;
+    }
+
+    let content_type = match bundle.kind {
+        AssetKind::Style => "text/css; charset=utf-8",
+        AssetKind::Script => "text/javascript; charset=utf-8",
+    };
+    let mut response = Response::new(Body::from(bundle.contents.clone()));
+    let response_headers = response.headers_mut();
+    response_headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response_headers.insert(header::ETAG, etag);
+    response_headers.insert(header::CACHE_CONTROL, cache_control);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pages_components::assets::AssetManifest;
+
+    fn bundler() -> Bundler {
+        let manifest = AssetManifest::from_json(
+            r#"{"global.css": "global.518cc205.css", "components/button/button.css": "components/button/button.0a1b2c3d.css", "home_page.js": "home_page.6bfd6122.js"}"#,
+        )
+        .unwrap();
+        Bundler::new(AssetContext::new(Arc::new(manifest)))
+    }
+
+    #[test]
+    fn bundles_accept_manifest_files_of_their_kind() {
+        let bundler = bundler();
+        assert!(bundler.accepts(AssetKind::Style, "global.css"));
+        assert!(bundler.accepts(AssetKind::Style, "components/button/button.css"));
+        assert!(bundler.accepts(AssetKind::Script, "home_page.js"));
+        assert!(!bundler.accepts(AssetKind::Script, "global.css"));
+        assert!(!bundler.accepts(AssetKind::Style, "missing.css"));
+    }
+
+    #[test]
+    fn bundles_reject_unsafe_names() {
+        let bundler = bundler();
+        assert!(!bundler.accepts(AssetKind::Style, "../global.css"));
+        assert!(!bundler.accepts(AssetKind::Style, "/global.css"));
+        assert!(!bundler.accepts(AssetKind::Style, "components//button.css"));
+        assert!(!bundler.accepts(AssetKind::Style, "global.css?x"));
+    }
+
+    #[test]
+    fn unknown_bundles_are_not_rebuilt_from_foreign_files() {
+        let bundler = bundler();
+        assert!(bundler.rebuild("0123456789abcdef.css", "global.css,../secret.css").is_none());
+        assert!(bundler.rebuild("0123456789abcdef.txt", "global.css").is_none());
+    }
+}
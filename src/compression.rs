@@ -0,0 +1,89 @@
+// src/compression.rs
+
+//! Negotiated compression for dynamic responses (server-rendered pages, JSON).
+//!
+//! The encoding (brotli, zstd or gzip) is picked from the request's
+//! `Accept-Encoding`. A response is only compressed when it is at least
+//! `min_size` bytes and its `Content-Type` is in the allowlist. Static files
+//! are not covered: they are precompressed at build time.
+
+use std::sync::Arc;
+
+use axum::http::{header, Response};
+use serde::Deserialize;
+use tower_http::compression::predicate::{And, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+
+pub const DEFAULT_MIN_SIZE: u16 = 1024;
+pub const DEFAULT_CONTENT_TYPES: &[&str] = &[
+    "text/html",
+    "application/json",
+    "text/plain",
+    "text/css",
+    "text/javascript",
+    "application/javascript",
+    "image/svg+xml",
+];
+
+/// The `[compression]` table of the config file:
+///
+/// ```toml
+/// [compression]
+/// min_size = 1024
+/// content_types = ["text/html", "application/json"]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompressionConfig {
+    /// Responses smaller than this many bytes are sent uncompressed.
+    pub min_size: u16,
+    /// Media types (without parameters) that may be compressed.
+    pub content_types: Vec<String>,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            min_size: DEFAULT_MIN_SIZE,
+            content_types: DEFAULT_CONTENT_TYPES.iter().map(|value| value.to_string()).collect(),
+        }
+    }
+}
+
+/// Only compresses responses whose media type is in the list.
+#[derive(Debug, Clone)]
+pub struct ContentTypeAllowlist {
+    content_types: Arc<[String]>,
+}
+
+impl Predicate for ContentTypeAllowlist {
+    fn should_compress<B>(&self, response: &Response<B>) -> bool
+    where
+        B: axum::body::HttpBody,
+    {
+        let Some(content_type) = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+        else {
+            return false;
+        };
+        let media_type = content_type.split(';').next().unwrap_or("").trim();
+        self.content_types
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(media_type))
+    }
+}
+
+/// Builds the compression layer for the dynamic routes.
+pub fn layer(config: &CompressionConfig) -> CompressionLayer<And<SizeAbove, ContentTypeAllowlist>> {
+    let allowlist = ContentTypeAllowlist {
+        content_types: config.content_types.clone().into(),
+    };
+
+    CompressionLayer::new()
+        .br(true)
+        .zstd(true)
+        .gzip(true)
+        .compress_when(SizeAbove::new(config.min_size).and(allowlist))
+}
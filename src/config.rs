@@ -0,0 +1,360 @@
+// src/config.rs
+
+//! Runtime configuration.
+//!
+//! Every setting can come from four places. When a setting is given in more
+//! than one, the first one in this list wins:
+//!
+//! 1. Command-line flags (`--port 8080`)
+//! 2. Environment variables (`APP_PORT=8080`)
+//! 3. The TOML file passed with `--config` / `APP_CONFIG` (`port = 8080`)
+//! 4. Built-in defaults
+//!
+//! | Setting       | Flag            | Environment      | TOML key      | Default                       |
+//! |---------------|-----------------|------------------|---------------|-------------------------------|
+//! | host          | `--host`        | `APP_HOST`       | `host`        | `0.0.0.0`                     |
+//! | port          | `--port`        | `APP_PORT`       | `port`        | `3000`                        |
+//! | static root   | `--static-dir`  | `APP_STATIC_DIR` | `static_dir`  | `static/` next to the binary (`static-namespaced/` with the `namespaced-assets` feature) |
+//! | log filter    | `--log`         | `RUST_LOG`       | `log`         | see `DEFAULT_LOG_FILTER`      |
+//! | environment   | `--env`         | `APP_ENV`        | `environment` | `development`                 |
+//! | drain timeout | `--shutdown-timeout` | `APP_SHUTDOWN_TIMEOUT` | `shutdown_timeout` | `30` (seconds) |
+//! | asset bundles | `--bundle-assets` | `APP_BUNDLE_ASSETS` | `bundle_assets` | `false` (see `bundles`) |
+//! | public origin | `--base-url`    | `APP_BASE_URL`   | `base_url`    | none (relative URLs)          |
+//!
+//! Example file:
+//!
+//! ```toml
+//! host = "127.0.0.1"
+//! port = 8080
+//! static_dir = "/srv/site/static"
+//! log = "info,tower_http=warn"
+//! environment = "production"
+//! shutdown_timeout = 10
+//! bundle_assets = true
+//! base_url = "https://example.com"
+//!
+//! # Cache-Control overrides for static files, first match wins
+//! # (see `static_files::CacheRule`). Only available in the file.
+//! [[cache_rules]]
+//! pattern = "/*.webp"
+//! cache_control = "public, max-age=86400"
+//!
+//! # Compression of pages and API responses (see `compression::CompressionConfig`).
+//! # Only available in the file.
+//! [compression]
+//! min_size = 1024
+//! content_types = ["text/html", "application/json"]
+//! ```
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::Parser;
+use serde::Deserialize;
+
+use crate::compression::CompressionConfig;
+use crate::static_files::CacheRule;
+
+pub const DEFAULT_HOST: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+pub const DEFAULT_PORT: u16 = 3000;
+pub const DEFAULT_LOG_FILTER: &str = "info,my_ssr_server=debug,tower_http=debug,axum::rejection=trace";
+pub const DEFAULT_ENVIRONMENT: &str = "development";
+pub const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+
+/// Command-line flags; each one can also be set through its environment variable.
+#[derive(Debug, Parser)]
+#[command(version, about = "Server-side rendered Rust web application")]
+pub struct Cli {
+    /// Optional TOML configuration file.
+    #[arg(long = "config", env = "APP_CONFIG")]
+    pub config_file: Option<PathBuf>,
+
+    /// Address to bind to.
+    #[arg(long, env = "APP_HOST")]
+    pub host: Option<IpAddr>,
+
+    /// Port to listen on.
+    #[arg(long, env = "APP_PORT")]
+    pub port: Option<u16>,
+
+    /// Directory the static assets are served from.
+    #[arg(long, env = "APP_STATIC_DIR")]
+    pub static_dir: Option<PathBuf>,
+
+    /// Tracing filter directives (e.g. "info,tower_http=debug").
+    #[arg(long = "log", env = "RUST_LOG")]
+    pub log_filter: Option<String>,
+
+    /// Name of the environment (e.g. "development", "staging", "production").
+    #[arg(long = "env", env = "APP_ENV")]
+    pub environment: Option<String>,
+
+    /// Seconds to wait for in-flight requests after SIGINT/SIGTERM.
+    #[arg(long, env = "APP_SHUTDOWN_TIMEOUT")]
+    pub shutdown_timeout: Option<u64>,
+
+    /// Serve each page's stylesheets and scripts as one bundle per tag group.
+    #[arg(long, env = "APP_BUNDLE_ASSETS", num_args = 0..=1, default_missing_value = "true")]
+    pub bundle_assets: Option<bool>,
+
+    /// Public origin of the site (e.g. "https://example.com"), used for the
+    /// canonical and Open Graph URLs.
+    #[arg(long, env = "APP_BASE_URL")]
+    pub base_url: Option<String>,
+}
+
+/// The optional configuration file. Every key is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    pub host: Option<IpAddr>,
+    pub port: Option<u16>,
+    pub static_dir: Option<PathBuf>,
+    pub log: Option<String>,
+    pub environment: Option<String>,
+    pub shutdown_timeout: Option<u64>,
+    pub bundle_assets: Option<bool>,
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub cache_rules: Vec<CacheRule>,
+    #[serde(default)]
+    pub compression: CompressionConfig,
+}
+
+/// The resolved configuration the server runs with.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub host: IpAddr,
+    pub port: u16,
+    pub static_dir: PathBuf,
+    pub log_filter: String,
+    pub environment: String,
+    pub shutdown_timeout: Duration,
+    pub bundle_assets: bool,
+    /// Without a trailing `/`; `None` leaves the page URLs relative.
+    pub base_url: Option<String>,
+    pub cache_rules: Vec<CacheRule>,
+    pub compression: CompressionConfig,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The configuration file couldn't be read.
+    Read { path: PathBuf, source: std::io::Error },
+    /// The configuration file isn't valid TOML or has unknown/mistyped keys.
+    Parse { path: PathBuf, source: toml::de::Error },
+    /// No static directory was configured and the executable's location is unknown.
+    StaticDir(std::io::Error),
+    /// The base URL isn't an absolute `http(s)://` URL.
+    BaseUrl(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, source } => {
+                write!(f, "failed to read config file {:?}: {}", path, source)
+            }
+            ConfigError::Parse { path, source } => {
+                write!(f, "invalid config file {:?}: {}", path, source)
+            }
+            ConfigError::StaticDir(source) => {
+                write!(f, "failed to locate the default static directory: {}", source)
+            }
+            ConfigError::BaseUrl(base_url) => {
+                write!(f, "base URL {:?} must start with http:// or https://", base_url)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Loads the configuration from the process arguments, environment and config file.
+    pub fn load() -> Result<Self, ConfigError> {
+        let cli = Cli::parse();
+        let file = match &cli.config_file {
+            Some(path) => read_file(path)?,
+            None => FileConfig::default(),
+        };
+        Self::resolve(cli, file)
+    }
+
+    /// Merges flags/environment (`cli`) over the file over the defaults.
+    pub fn resolve(cli: Cli, file: FileConfig) -> Result<Self, ConfigError> {
+        let static_dir = match cli.static_dir.or(file.static_dir) {
+            Some(static_dir) => static_dir,
+            None => default_static_dir()?,
+        };
+        let base_url = match cli.base_url.or(file.base_url) {
+            Some(base_url) if base_url.starts_with("https://") || base_url.starts_with("http://") => {
+                Some(base_url.trim_end_matches('/').to_string())
+            }
+            Some(base_url) => return Err(ConfigError::BaseUrl(base_url)),
+            None => None,
+        };
+
+        Ok(Self {
+            host: cli.host.or(file.host).unwrap_or(DEFAULT_HOST),
+            port: cli.port.or(file.port).unwrap_or(DEFAULT_PORT),
+            static_dir,
+            log_filter: cli
+                .log_filter
+                .or(file.log)
+                .unwrap_or_else(|| DEFAULT_LOG_FILTER.to_string()),
+            environment: cli
+                .environment
+                .or(file.environment)
+                .unwrap_or_else(|| DEFAULT_ENVIRONMENT.to_string()),
+            shutdown_timeout: Duration::from_secs(
+                cli.shutdown_timeout
+                    .or(file.shutdown_timeout)
+                    .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT_SECS),
+            ),
+            bundle_assets: cli.bundle_assets.or(file.bundle_assets).unwrap_or(false),
+            base_url,
+            cache_rules: file.cache_rules,
+            compression: file.compression,
+        })
+    }
+
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.host, self.port)
+    }
+}
+
+fn read_file(path: &Path) -> Result<FileConfig, ConfigError> {
+    let contents = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    toml::from_str(&contents).map_err(|source| ConfigError::Parse {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// The folder next to the executable where `build.rs` staged the assets for
+/// this build's features (`static/` or `static-namespaced/`).
+fn default_static_dir() -> Result<PathBuf, ConfigError> {
+    let current_exe_path = std::env::current_exe().map_err(ConfigError::StaticDir)?;
+    let current_dir = current_exe_path
+        .parent()
+        .expect("Failed to get parent directory of executable");
+    Ok(current_dir.join(env!("STAGED_STATIC_DIR")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cli() -> Cli {
+        Cli {
+            config_file: None,
+            host: None,
+            port: None,
+            static_dir: Some(PathBuf::from("static")),
+            log_filter: None,
+            environment: None,
+            shutdown_timeout: None,
+            bundle_assets: None,
+            base_url: None,
+        }
+    }
+
+    fn file(toml: &str) -> FileConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn unset_settings_fall_back_to_the_defaults() {
+        let config = Config::resolve(cli(), FileConfig::default()).unwrap();
+        assert_eq!(config.socket_addr(), SocketAddr::new(DEFAULT_HOST, DEFAULT_PORT));
+        assert_eq!(config.log_filter, DEFAULT_LOG_FILTER);
+        assert_eq!(config.environment, DEFAULT_ENVIRONMENT);
+        assert_eq!(config.shutdown_timeout, Duration::from_secs(DEFAULT_SHUTDOWN_TIMEOUT_SECS));
+        assert!(!config.bundle_assets);
+        assert_eq!(config.base_url, None);
+    }
+
+    #[test]
+    fn the_file_overrides_the_defaults() {
+        let file = file(
+            r#"
+            host = "127.0.0.1"
+            port = 8080
+            static_dir = "/srv/site/static"
+            environment = "staging"
+            shutdown_timeout = 10
+            bundle_assets = true
+            "#,
+        );
+        let config = Config::resolve(Cli { static_dir: None, ..cli() }, file).unwrap();
+        assert_eq!(config.socket_addr(), "127.0.0.1:8080".parse::<SocketAddr>().unwrap());
+        assert_eq!(config.static_dir, PathBuf::from("/srv/site/static"));
+        assert_eq!(config.environment, "staging");
+        assert_eq!(config.shutdown_timeout, Duration::from_secs(10));
+        assert!(config.bundle_assets);
+    }
+
+    #[test]
+    fn flags_and_environment_override_the_file() {
+        let flags = Cli {
+            port: Some(9000),
+            static_dir: Some(PathBuf::from("/tmp/static")),
+            environment: Some(String::from("production")),
+            bundle_assets: Some(false),
+            ..cli()
+        };
+        let file = file(
+            r#"
+            host = "127.0.0.1"
+            port = 8080
+            static_dir = "/srv/site/static"
+            environment = "staging"
+            bundle_assets = true
+            "#,
+        );
+        let config = Config::resolve(flags, file).unwrap();
+        assert_eq!(config.socket_addr(), "127.0.0.1:9000".parse::<SocketAddr>().unwrap());
+        assert_eq!(config.static_dir, PathBuf::from("/tmp/static"));
+        assert_eq!(config.environment, "production");
+        assert!(!config.bundle_assets);
+    }
+
+    #[test]
+    fn base_urls_lose_their_trailing_slashes() {
+        let flags = Cli {
+            base_url: Some(String::from("https://example.com//")),
+            ..cli()
+        };
+        let config = Config::resolve(flags, file(r#"base_url = "http://file.example""#)).unwrap();
+        assert_eq!(config.base_url.as_deref(), Some("https://example.com"));
+
+        let config = Config::resolve(cli(), file(r#"base_url = "http://file.example/""#)).unwrap();
+        assert_eq!(config.base_url.as_deref(), Some("http://file.example"));
+    }
+
+    #[test]
+    fn base_urls_must_be_http_or_https() {
+        for base_url in ["example.com", "ftp://example.com", "//example.com", "/"] {
+            let flags = Cli {
+                base_url: Some(String::from(base_url)),
+                ..cli()
+            };
+            assert!(
+                matches!(Config::resolve(flags, FileConfig::default()), Err(ConfigError::BaseUrl(url)) if url == base_url),
+                "{base_url:?} was accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_file_keys_are_rejected() {
+        let error = toml::from_str::<FileConfig>("prot = 8080").unwrap_err();
+        assert!(error.to_string().contains("unknown field `prot`"), "{error}");
+    }
+}
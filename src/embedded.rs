@@ -0,0 +1,101 @@
+// src/embedded.rs
+
+//! Static assets compiled into the binary (cargo feature `embed`).
+//!
+//! `build.rs` stages the assets exactly as for the disk mode and generates a
+//! table that pulls each of them in with `include_bytes!`, so the binary can be
+//! moved or started from any directory. Responses carry the MIME type guessed
+//! from the file name, a strong `ETag` computed at build time, and the `.br` /
+//! `.gz` variant when the client accepts it. Caching headers and `304`s are
+//! added by `static_files::cache_headers`, the same as for `ServeDir`.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+/// One staged file, e.g. `global.518cc205.css` or `global.518cc205.css.br`.
+pub struct EmbeddedAsset {
+    pub name: &'static str,
+    pub etag: &'static str,
+    pub contents: &'static [u8],
+}
+
+include!(concat!(env!("OUT_DIR"), "/embedded_assets.rs"));
+
+fn assets() -> &'static HashMap<&'static str, &'static EmbeddedAsset> {
+    static ASSETS: OnceLock<HashMap<&'static str, &'static EmbeddedAsset>> = OnceLock::new();
+    ASSETS.get_or_init(|| EMBEDDED_ASSETS.iter().map(|asset| (asset.name, asset)).collect())
+}
+
+/// Number of embedded files, precompressed variants included.
+pub fn len() -> usize {
+    EMBEDDED_ASSETS.len()
+}
+
+/// The uncompressed contents of the embedded file `name` (e.g. `global.518cc205.css`).
+pub fn contents(name: &str) -> Option<&'static [u8]> {
+    assets().get(name).map(|asset| asset.contents)
+}
+
+/// Serves `GET`/`HEAD` requests for embedded assets; anything else is a 404.
+pub async fn serve(request: Request) -> Response {
+    let name = request.uri().path().trim_start_matches('/');
+    let Some(asset) = assets().get(name) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let accept_encoding = request
+        .headers()
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+
+    // Prefer brotli, then gzip, when the client accepts it and a variant exists.
+    let (served, encoding) = [("br", "br"), ("gzip", "gz")]
+        .iter()
+        .filter(|(encoding, _)| accepts(accept_encoding, encoding))
+        .find_map(|(encoding, suffix)| {
+            assets()
+                .get(format!("{}.{}", name, suffix).as_str())
+                .map(|variant| (*variant, Some(*encoding)))
+        })
+        .unwrap_or((*asset, None));
+
+    let mime = mime_guess::from_path(name).first_or_octet_stream();
+
+    let mut response = Response::new(Body::from(served.contents));
+    let headers = response.headers_mut();
+    if let Ok(content_type) = HeaderValue::from_str(mime.as_ref()) {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(served.contents.len()));
+    if let Ok(etag) = HeaderValue::from_str(&format!("\"{}\"", served.etag)) {
+        headers.insert(header::ETAG, etag);
+    }
+    if let Some(encoding) = encoding {
+        headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding));
+    }
+    response
+}
+
+/// Whether `accept_encoding` lists `encoding` with a non-zero quality.
+fn accepts(accept_encoding: &str, encoding: &str) -> bool {
+    accept_encoding.split(',').any(|entry| {
+        let mut parts = entry.split(';');
+        let name = parts.next().unwrap_or("").trim();
+        let rejected = parts.any(|parameter| {
+            parameter
+                .trim()
+                .strip_prefix("q=")
+                .and_then(|quality| quality.trim().parse::<f32>().ok())
+                .is_some_and(|quality| quality == 0.0)
+        });
+        name.eq_ignore_ascii_case(encoding) && !rejected
+    })
+}
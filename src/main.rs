@@ -1,90 +1,141 @@
 use axum::{
-    routing::{get, post},
-    http::StatusCode,
-    response::{Html, Json},
+    middleware,
+    routing::get,
+    response::Json,
     Router,
 };
 use tokio::net::TcpListener;
-use std::net::SocketAddr;
-use std::path::PathBuf;
-use std::env;
 
+use tower::ServiceBuilder;
+#[cfg(not(feature = "embed"))]
 use tower_http::services::ServeDir;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use chrono::Utc;
 
-use html_pages::home_page;
-use pages_components::ButtonComponent;
-
+mod asset_reader;
+mod bundles;
+mod compression;
+mod config;
+#[cfg(feature = "embed")]
+mod embedded;
+mod shutdown;
+mod static_files;
+
+// The asset pipeline helpers of `build.rs`, included here for their unit tests.
+#[cfg(test)]
+#[path = "../build/minify.rs"]
+mod minify;
+#[cfg(test)]
+#[path = "../build/scope_css.rs"]
+mod scope_css;
+
+use std::collections::HashSet;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use config::Config;
+use pages_components::assets::{AssetContext, AssetManifest};
+use static_files::CachePolicy;
+
+// Logical -> fingerprinted asset names, generated by build.rs for the assets
+// staged alongside this binary.
+const ASSET_MANIFEST: &str = include_str!(concat!(env!("OUT_DIR"), "/asset-manifest.json"));
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
+    let config = Config::load().unwrap_or_else(|error| {
+        eprintln!("Configuration error: {}", error);
+        std::process::exit(2);
+    });
+
     tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info,my_ssr_server=debug,tower_http=debug,axum::rejection=trace".into()),
-        )
+        .with(tracing_subscriber::EnvFilter::new(&config.log_filter))
         .with(tracing_subscriber::fmt::layer())
         .init();
 
     println!("Server application starting...");
-
-    let current_exe_path = env::current_exe()
-        .expect("Failed to get current executable path");
-
-    let current_dir = current_exe_path.parent()
-        .expect("Failed to get parent directory of executable");
-
-    let static_files_path = current_dir.join("static");
-
-    tracing::info!("Serving static files from: {:?}", static_files_path);
-
-
-    let app = Router::new()
-        .route("/", get(index_handler))
+    tracing::info!("Environment: {}", config.environment);
+
+    let manifest = AssetManifest::from_json(ASSET_MANIFEST)
+        .expect("build.rs should write a valid asset manifest");
+    tracing::info!("Loaded asset manifest with {} entries", manifest.len());
+    let cache_policy = Arc::new(CachePolicy::new(
+        config.cache_rules.clone(),
+        manifest.fingerprinted_names().map(String::from).collect::<HashSet<String>>(),
+    ));
+    let manifest = Arc::new(manifest);
+    let reader = asset_reader::StaticAssetReader::new(Arc::clone(&manifest), config.static_dir.clone());
+    let mut assets = AssetContext::new(manifest).with_reader(Arc::new(reader));
+
+    // Static assets come from the `static/` folder, or from the binary itself
+    // when built with `--features embed`.
+    #[cfg(not(feature = "embed"))]
+    let static_service = {
+        let static_files_path = config.static_dir.clone();
+        tracing::info!("Serving static files from: {:?}", static_files_path);
+        ServeDir::new(static_files_path)
+            .precompressed_br()
+            .precompressed_gzip()
+    };
+    #[cfg(feature = "embed")]
+    let static_service = {
+        tracing::info!("Serving {} static files embedded in the binary", embedded::len());
+        tracing::debug!("Static directory {:?} is not used in embed mode", config.static_dir);
+        get(embedded::serve)
+    };
+
+    // Per-page bundles are built on the first render that needs them.
+    let mut router = Router::new();
+    if config.bundle_assets {
+        let bundler = Arc::new(bundles::Bundler::new(assets.clone()));
+        assets = assets.with_bundler(bundler.clone());
+        router = router.route(
+            &format!("{}:name", bundles::BUNDLE_ROUTE_PREFIX),
+            get(bundles::serve).with_state(bundler),
+        );
+        tracing::info!("Serving page assets as bundles under {}", bundles::BUNDLE_ROUTE_PREFIX);
+    }
+
+    if config.base_url.is_none() {
+        tracing::warn!("No base URL configured; canonical and Open Graph URLs stay relative");
+    }
+    let site = html_pages::Site {
+        base_url: config.base_url.clone(),
+        assets,
+    };
+
+    let app = html_pages::registry()
+        .mount(router, site)
         .route("/api/data", get(api_data_handler))
-        .fallback_service(ServeDir::new(static_files_path));
-
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
+        // Only the routes above are compressed on the fly; static files are precompressed.
+        .layer(compression::layer(&config.compression))
+        .fallback_service(
+            ServiceBuilder::new()
+                .layer(middleware::from_fn_with_state(cache_policy, static_files::cache_headers))
+                .service(static_service),
+        );
+
+    let addr = config.socket_addr();
     tracing::info!("Server listening on {}", addr);
 
-    let listener = TcpListener::bind(addr).await
-        .expect("Failed to bind TCP listener");
-
-    axum::serve(listener, app)
-        .await
-        .expect("Failed to start server");
-}
-
-async fn index_handler() -> Html<String> {
-    tracing::info!("Received request for /");
-
-    let page_level_data = vec![
-        String::from("<p>This is dynamic page-level data.</p>"),
-        String::from("<p>More data here!</p>"),
-    ];
-
-    let css_files_markup = vec![
-        String::from(r#"<link rel="stylesheet" href="global.css">"#),
-    ];
-
-    let components_markup = vec![
-        String::from(r#"<div class="some-other-component">This is a generic component placeholder!</div>"#),
-    ];
-
-    let js_files_markup = vec![
-        String::from(r#"<script src="/home_page.js"></script>"#),
-    ];
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(error) => {
+            tracing::error!("Failed to bind TCP listener on {}: {}", addr, error);
+            shutdown::flush_output();
+            return ExitCode::FAILURE;
+        }
+    };
 
-    let full_html = html_pages::home_page::print_page(
-        &page_level_data,
-        &css_files_markup,
-        &components_markup,
-        &js_files_markup,
-    );
+    let outcome = shutdown::run(
+        |signal| axum::serve(listener, app).with_graceful_shutdown(signal).into_future(),
+        config.shutdown_timeout,
+    )
+    .await;
 
-    Html(full_html)
+    shutdown::flush_output();
+    outcome.exit_code()
 }
 
 async fn api_data_handler() -> Json<serde_json::Value> {
@@ -98,4 +149,37 @@ async fn api_data_handler() -> Json<serde_json::Value> {
             "key2": 123
         }
     }))
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use html_pages::PageRequest;
+
+    #[test]
+    fn every_stylesheet_and_script_of_the_pages_is_staged() {
+        let manifest = Arc::new(AssetManifest::from_json(ASSET_MANIFEST).unwrap());
+        let staged: HashSet<&str> = manifest.fingerprinted_names().collect();
+        let request = PageRequest {
+            site: Arc::new(html_pages::Site {
+                base_url: None,
+                assets: AssetContext::new(Arc::clone(&manifest)),
+            }),
+            ..PageRequest::default()
+        };
+
+        for page in html_pages::registry().pages() {
+            let html = page.render(&request);
+            let urls: Vec<&str> = html
+                .split(r#"href="/"#)
+                .chain(html.split(r#"src="/"#))
+                .skip(1)
+                .filter_map(|rest| rest.split_once('"').map(|(url, _)| url))
+                .filter(|url| url.ends_with(".css") || url.ends_with(".js"))
+                .collect();
+            assert!(!urls.is_empty(), "{} links no assets", page.path());
+            for url in urls {
+                assert!(staged.contains(url), "{} links /{}, which the build didn't stage", page.path(), url);
+            }
+        }
+    }
+}
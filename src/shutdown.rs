@@ -0,0 +1,120 @@
+// src/shutdown.rs
+
+//! Graceful shutdown.
+//!
+//! On SIGINT (Ctrl+C) or SIGTERM the server stops accepting connections and
+//! lets in-flight requests finish. If they are still running when the drain
+//! timeout expires, the remaining connections are dropped and the process
+//! exits with a failure status so the deploy tooling can tell.
+//!
+//! Exit codes: `0` drained cleanly, `1` server error, `3` drain timed out.
+
+use std::future::Future;
+use std::io::Write;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+/// How a server run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// A shutdown signal arrived and every in-flight request completed.
+    Drained,
+    /// A shutdown signal arrived but requests were still running at the deadline.
+    DrainTimedOut,
+    /// The server stopped because of an I/O error.
+    Failed,
+}
+
+impl Outcome {
+    pub fn exit_code(self) -> ExitCode {
+        match self {
+            Outcome::Drained => ExitCode::SUCCESS,
+            Outcome::DrainTimedOut => ExitCode::from(3),
+            Outcome::Failed => ExitCode::FAILURE,
+        }
+    }
+}
+
+/// Resolves when the process receives SIGINT or SIGTERM, returning the signal name.
+pub async fn signal() -> &'static str {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install the Ctrl+C handler");
+        "SIGINT"
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install the SIGTERM handler")
+            .recv()
+            .await;
+        "SIGTERM"
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<&'static str>();
+
+    tokio::select! {
+        name = ctrl_c => name,
+        name = terminate => name,
+    }
+}
+
+/// Runs `serve` until it finishes draining, or until `drain_timeout` after a
+/// shutdown signal, whichever comes first.
+///
+/// `serve` receives the future to pass to `with_graceful_shutdown`.
+pub async fn run<F, S>(serve: S, drain_timeout: Duration) -> Outcome
+where
+    S: FnOnce(std::pin::Pin<Box<dyn Future<Output = ()> + Send>>) -> F,
+    F: Future<Output = std::io::Result<()>>,
+{
+    let (signalled_tx, mut signalled_rx) = watch::channel(false);
+
+    let shutdown = Box::pin(async move {
+        let name = signal().await;
+        tracing::info!(
+            "Received {}, draining in-flight requests (timeout {:?})",
+            name,
+            drain_timeout
+        );
+        let _ = signalled_tx.send(true);
+    });
+
+    let deadline = async move {
+        // Only start counting once the signal arrived.
+        while !*signalled_rx.borrow_and_update() {
+            if signalled_rx.changed().await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        }
+        tokio::time::sleep(drain_timeout).await;
+    };
+
+    tokio::select! {
+        result = serve(shutdown) => match result {
+            Ok(()) => {
+                tracing::info!("All connections drained, shutting down");
+                Outcome::Drained
+            }
+            Err(error) => {
+                tracing::error!("Server error: {}", error);
+                Outcome::Failed
+            }
+        },
+        _ = deadline => {
+            tracing::warn!("Drain timeout expired, dropping remaining connections");
+            Outcome::DrainTimedOut
+        }
+    }
+}
+
+/// Flushes anything the tracing subscriber wrote to stdout/stderr before exit.
+pub fn flush_output() {
+    let _ = std::io::stdout().flush();
+    let _ = std::io::stderr().flush();
+}
@@ -0,0 +1,286 @@
+// src/static_files.rs
+
+//! Caching headers for the static assets served from the `static/` folder.
+//!
+//! - Fingerprinted files (`button_component.3f9a1c2b.css`, listed in the asset
+//!   manifest) never change under the same name, so they are cached for a year
+//!   and marked `immutable`.
+//! - Unhashed files (`global.css`, `favicon.ico`, ...) get a short lifetime and
+//!   must be revalidated.
+//! - Both can be overridden per path pattern from the config file; the first
+//!   matching rule wins.
+//!
+//! Every successful response carries an `ETag` (derived from the file size,
+//! modification time and content encoding) next to the `Last-Modified` set by
+//! `ServeDir`, and matching `If-None-Match` / `If-Modified-Since` requests get
+//! a `304`.
+//!
+//! `build.rs` writes `.gz`/`.br` siblings for compressible assets; `ServeDir`
+//! picks one from `Accept-Encoding` and this layer adds `Vary: Accept-Encoding`
+//! so shared caches keep the variants apart.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+pub const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+pub const REVALIDATE_CACHE_CONTROL: &str = "public, max-age=300, must-revalidate";
+
+/// One `[[cache_rules]]` entry of the config file:
+///
+/// ```toml
+/// [[cache_rules]]
+/// pattern = "/*.webp"
+/// cache_control = "public, max-age=86400"
+/// ```
+///
+/// `*` in `pattern` matches any run of characters (including `/`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CacheRule {
+    pub pattern: String,
+    pub cache_control: String,
+}
+
+/// Decides the `Cache-Control` value for each static path.
+#[derive(Debug, Clone)]
+pub struct CachePolicy {
+    rules: Vec<CacheRule>,
+    fingerprinted: HashSet<String>,
+}
+
+impl CachePolicy {
+    /// `fingerprinted` holds the hashed paths (relative to `static/`) from the asset manifest.
+    pub fn new(rules: Vec<CacheRule>, fingerprinted: HashSet<String>) -> Self {
+        Self { rules, fingerprinted }
+    }
+
+    pub fn cache_control(&self, path: &str) -> &str {
+        if let Some(rule) = self.rules.iter().find(|rule| glob_match(&rule.pattern, path)) {
+            return &rule.cache_control;
+        }
+        if self.fingerprinted.contains(path.trim_start_matches('/')) {
+            IMMUTABLE_CACHE_CONTROL
+        } else {
+            REVALIDATE_CACHE_CONTROL
+        }
+    }
+}
+
+/// Middleware placed in front of `ServeDir`.
+pub async fn cache_headers(
+    State(policy): State<Arc<CachePolicy>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return next.run(request).await;
+    }
+
+    let path = request.uri().path().to_string();
+    let if_none_match = request.headers().get(header::IF_NONE_MATCH).cloned();
+    if if_none_match.is_some() {
+        // `If-None-Match` takes precedence; don't let `ServeDir` answer 304 on the date alone.
+        request.headers_mut().remove(header::IF_MODIFIED_SINCE);
+    }
+
+    let mut response = next.run(request).await.into_response();
+    let status = response.status();
+    if status != StatusCode::OK && status != StatusCode::NOT_MODIFIED {
+        return response;
+    }
+
+    let cache_control = HeaderValue::from_str(policy.cache_control(&path))
+        .unwrap_or_else(|_| HeaderValue::from_static(REVALIDATE_CACHE_CONTROL));
+    response.headers_mut().insert(header::CACHE_CONTROL, cache_control);
+    response
+        .headers_mut()
+        .insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+
+    // Keep a validator set by the inner service (embedded assets), otherwise derive one.
+    let etag = response
+        .headers()
+        .get(header::ETAG)
+        .cloned()
+        .or_else(|| etag(response.headers()));
+    if status == StatusCode::OK
+        && let Some(etag) = etag
+    {
+        if let Some(if_none_match) = if_none_match
+            && etag_matches(&if_none_match, &etag)
+        {
+            return not_modified(response.headers(), etag);
+        }
+        response.headers_mut().insert(header::ETAG, etag);
+    }
+
+    response
+}
+
+/// Weak validator built from the `Content-Length`, `Last-Modified` and
+/// `Content-Encoding` headers, so each precompressed variant has its own tag.
+fn etag(headers: &HeaderMap) -> Option<HeaderValue> {
+    let length = headers.get(header::CONTENT_LENGTH)?.to_str().ok()?;
+    let modified = headers.get(header::LAST_MODIFIED)?.to_str().ok()?;
+    let modified = httpdate::parse_http_date(modified).ok()?;
+    let seconds = modified
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    let encoding = headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| format!("-{}", value))
+        .unwrap_or_default();
+    HeaderValue::from_str(&format!(
+        "W/\"{:x}-{:x}{}\"",
+        length.parse::<u64>().ok()?,
+        seconds,
+        encoding
+    ))
+    .ok()
+}
+
+/// Weak comparison against an `If-None-Match` list (or `*`).
+pub fn etag_matches(if_none_match: &HeaderValue, etag: &HeaderValue) -> bool {
+    let Ok(if_none_match) = if_none_match.to_str() else {
+        return false;
+    };
+    let Ok(etag) = etag.to_str() else {
+        return false;
+    };
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    if_none_match
+        .split(',')
+        .any(|candidate| candidate.trim() == "*" || opaque(candidate) == opaque(etag))
+}
+
+fn not_modified(headers: &HeaderMap, etag: HeaderValue) -> Response {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = StatusCode::NOT_MODIFIED;
+    for name in [header::CACHE_CONTROL, header::LAST_MODIFIED, header::VARY] {
+        if let Some(value) = headers.get(&name) {
+            response.headers_mut().insert(name, value.clone());
+        }
+    }
+    response.headers_mut().insert(header::ETAG, etag);
+    response
+}
+
+/// Matches `text` against `pattern`, where `*` stands for any run of characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*` at all: exact match.
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(if_none_match: &str, etag: &str) -> bool {
+        etag_matches(&HeaderValue::from_str(if_none_match).unwrap(), &HeaderValue::from_str(etag).unwrap())
+    }
+
+    #[test]
+    fn etag_matches_the_same_tag() {
+        assert!(matches(r#""abc""#, r#""abc""#));
+        assert!(!matches(r#""abc""#, r#""abd""#));
+    }
+
+    #[test]
+    fn etag_matches_weakly() {
+        assert!(matches(r#"W/"abc""#, r#""abc""#));
+        assert!(matches(r#""abc""#, r#"W/"abc""#));
+        assert!(matches(r#"W/"abc""#, r#"W/"abc""#));
+        assert!(!matches(r#"W/"abc""#, r#"W/"xyz""#));
+    }
+
+    #[test]
+    fn etag_matches_any_tag_of_a_list() {
+        assert!(matches(r#""one", W/"two", "three""#, r#"W/"two""#));
+        assert!(matches(r#""one","three""#, r#""three""#));
+        assert!(!matches(r#""one", "three""#, r#""two""#));
+    }
+
+    #[test]
+    fn etag_matches_star() {
+        assert!(matches("*", r#""abc""#));
+        assert!(matches(r#""one", *"#, r#"W/"abc""#));
+    }
+
+    #[test]
+    fn glob_without_star_matches_exactly() {
+        assert!(glob_match("/global.css", "/global.css"));
+        assert!(!glob_match("/global.css", "/global.css.map"));
+        assert!(!glob_match("/global.css", "/x/global.css"));
+    }
+
+    #[test]
+    fn glob_star_at_the_end() {
+        assert!(glob_match("/images/*", "/images/photo.webp"));
+        assert!(glob_match("/images/*", "/images/"));
+        assert!(glob_match("/images/*", "/images/2025/photo.webp"));
+        assert!(!glob_match("/images/*", "/img/photo.webp"));
+    }
+
+    #[test]
+    fn glob_star_at_the_start() {
+        assert!(glob_match("*.webp", "/images/photo.webp"));
+        assert!(glob_match("/*.webp", "/photo.webp"));
+        assert!(!glob_match("/*.webp", "/photo.webp.br"));
+    }
+
+    #[test]
+    fn glob_star_in_the_middle() {
+        assert!(glob_match("/components/*/style.css", "/components/button/style.css"));
+        assert!(glob_match("/components/*/style.css", "/components/a/b/style.css"));
+        assert!(!glob_match("/components/*/style.css", "/components/button/script.js"));
+        assert!(glob_match("/*.*.css", "/global.518cc205.css"));
+        assert!(!glob_match("/*.*.css", "/global.css"));
+    }
+
+    #[test]
+    fn glob_parts_must_not_overlap() {
+        // `ab` and `ba` can't share the `b` of `aba`.
+        assert!(!glob_match("*ab*ba", "aba"));
+        assert!(glob_match("*ab*ba", "abba"));
+        assert!(!glob_match("/a*a", "/a"));
+    }
+
+    #[test]
+    fn cache_rules_win_over_the_defaults() {
+        let policy = CachePolicy::new(
+            vec![CacheRule {
+                pattern: String::from("/*.webp"),
+                cache_control: String::from("public, max-age=86400"),
+            }],
+            HashSet::from([String::from("global.518cc205.css")]),
+        );
+        assert_eq!(policy.cache_control("/photo.webp"), "public, max-age=86400");
+        assert_eq!(policy.cache_control("/global.518cc205.css"), IMMUTABLE_CACHE_CONTROL);
+        assert_eq!(policy.cache_control("/global.css"), REVALIDATE_CACHE_CONTROL);
+    }
+}
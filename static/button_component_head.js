@@ -1 +0,0 @@
-console.log("Button component head JS loaded early!");
\ No newline at end of file